@@ -0,0 +1,249 @@
+//! Find the day crates in the repository, build them and run their solvers and tests.
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Days whose solvers print their final grid when called with `--grid`.
+pub const GRID_DAYS: [&str; 6] = ["dec-06", "dec-08", "dec-10", "dec-12", "dec-14", "dec-15"];
+
+/// One day crate, e.g. `dec-05` or `dec-11_p2`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Day {
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+impl Day {
+    /// Path of the release binary, named like the package in `Cargo.toml`.
+    fn binary(&self) -> io::Result<PathBuf> {
+        let manifest = std::fs::read_to_string(self.dir.join("Cargo.toml"))?;
+        let package = manifest
+            .lines()
+            .find_map(|line| line.strip_prefix("name = "))
+            .map(|name| name.trim_matches('"').to_string())
+            .unwrap_or_else(|| self.name.clone());
+        Ok(self.dir.join("target").join("release").join(package))
+    }
+
+    /// Build the solver in release mode.
+    pub fn build(&self) -> io::Result<bool> {
+        let status = Command::new("cargo")
+            .args(["build", "--release", "-q"])
+            .current_dir(&self.dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        Ok(status.success())
+    }
+
+    /// Run the (already built) solver in its own directory, so it finds its `input`.
+    pub fn run(&self, args: &[&str], timeout: Duration) -> io::Result<Output> {
+        let mut cmd = Command::new(self.binary()?);
+        cmd.args(args).current_dir(&self.dir);
+        run_with_timeout(&mut cmd, timeout)
+    }
+
+    /// Run the example tests of the day, `None` if they did not finish or did not compile.
+    pub fn test(&self, timeout: Duration) -> io::Result<Option<TestCounts>> {
+        let mut cmd = Command::new("cargo");
+        cmd.args(["test", "--release", "-q"]).current_dir(&self.dir);
+        let out = run_with_timeout(&mut cmd, timeout)?;
+        if out.status == Status::TimedOut {
+            return Ok(None);
+        }
+        Ok(TestCounts::from_output(&out.stdout))
+    }
+}
+
+/// All day crates directly below `root`, sorted by name.
+pub fn find_days(root: &Path) -> io::Result<Vec<Day>> {
+    let mut days = Vec::new();
+    for entry in std::fs::read_dir(root)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with("dec-") && entry.path().join("Cargo.toml").is_file() {
+            days.push(Day {
+                name,
+                dir: entry.path(),
+            });
+        }
+    }
+    days.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(days)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Finished,
+    Failed(Option<i32>),
+    TimedOut,
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Status::Finished => write!(f, "ok"),
+            Status::Failed(Some(code)) => write!(f, "failed (exit code {})", code),
+            Status::Failed(None) => write!(f, "failed"),
+            Status::TimedOut => write!(f, "timed out"),
+        }
+    }
+}
+
+/// Result of a process run, `stdout` holds everything printed until it exited or was killed.
+#[derive(Debug)]
+pub struct Output {
+    pub status: Status,
+    pub stdout: String,
+    pub elapsed: Duration,
+}
+
+/// Run a command and kill it if it is not done after `timeout`.
+pub fn run_with_timeout(cmd: &mut Command, timeout: Duration) -> io::Result<Output> {
+    let now = Instant::now();
+    // own process group, so a timeout also kills what the command started (e.g. cargo's tests)
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(cmd, 0);
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::null()).spawn()?;
+
+    // read in the background, otherwise a chatty process blocks on a full pipe
+    let buf = Arc::new(Mutex::new(Vec::new()));
+    let mut pipe = child.stdout.take().unwrap();
+    let reader = {
+        let buf = buf.clone();
+        thread::spawn(move || {
+            let mut chunk = [0; 4096];
+            while let Ok(n) = pipe.read(&mut chunk) {
+                if n == 0 {
+                    break;
+                }
+                buf.lock().unwrap().extend_from_slice(&chunk[..n]);
+            }
+        })
+    };
+
+    let status = wait_with_timeout(&mut child, timeout)?;
+    let elapsed = now.elapsed();
+    if status != Status::TimedOut {
+        reader.join().unwrap();
+    }
+    // after a timeout, take whatever was printed so far
+    let stdout = String::from_utf8_lossy(&buf.lock().unwrap()).to_string();
+    Ok(Output {
+        status,
+        stdout,
+        elapsed,
+    })
+}
+
+fn wait_with_timeout(child: &mut Child, timeout: Duration) -> io::Result<Status> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(exit) = child.try_wait()? {
+            return Ok(if exit.success() {
+                Status::Finished
+            } else {
+                Status::Failed(exit.code())
+            });
+        }
+        if Instant::now() >= deadline {
+            kill(child)?;
+            child.wait()?;
+            return Ok(Status::TimedOut);
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[cfg(unix)]
+fn kill(child: &mut Child) -> io::Result<()> {
+    let group = format!("-{}", child.id());
+    let status = Command::new("kill")
+        .args(["-KILL", "--", &group])
+        .stderr(Stdio::null())
+        .status()?;
+    if !status.success() {
+        child.kill()?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn kill(child: &mut Child) -> io::Result<()> {
+    child.kill()
+}
+
+/// Passed and failed tests, summed over all `test result:` lines of a `cargo test` run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TestCounts {
+    pub passed: usize,
+    pub failed: usize,
+}
+
+impl TestCounts {
+    fn from_output(stdout: &str) -> Option<TestCounts> {
+        let mut counts = None;
+        for line in stdout.lines().filter(|l| l.starts_with("test result:")) {
+            let total: &mut TestCounts = counts.get_or_insert(TestCounts {
+                passed: 0,
+                failed: 0,
+            });
+            // e.g. `test result: ok. 3 passed; 0 failed; 0 ignored; ...`
+            let words: Vec<&str> = line.split_whitespace().collect();
+            for pair in words.windows(2) {
+                let Ok(n) = pair[0].parse::<usize>() else {
+                    continue;
+                };
+                match pair[1].trim_end_matches(';') {
+                    "passed" => total.passed += n,
+                    "failed" => total.failed += n,
+                    _ => (),
+                }
+            }
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_from_output() {
+        let out = "running 3 tests\n...\ntest result: FAILED. 3 passed; 2 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.01s\n";
+        assert_eq!(
+            TestCounts::from_output(out),
+            Some(TestCounts {
+                passed: 3,
+                failed: 2
+            })
+        );
+        assert_eq!(TestCounts::from_output("error: could not compile"), None);
+    }
+
+    #[test]
+    fn test_run_with_timeout() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo started; sleep 5"]);
+        let out = run_with_timeout(&mut cmd, Duration::from_millis(200)).unwrap();
+        assert_eq!(out.status, Status::TimedOut);
+        assert_eq!(out.stdout, "started\n");
+
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "exit 3"]);
+        let out = run_with_timeout(&mut cmd, Duration::from_secs(5)).unwrap();
+        assert_eq!(out.status, Status::Failed(Some(3)));
+    }
+
+    #[test]
+    fn test_find_days() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        let days = find_days(&root).unwrap();
+        assert_eq!(days[0].name, "dec-01");
+        assert!(days.iter().all(|d| d.name.starts_with("dec-")));
+    }
+}
@@ -0,0 +1,164 @@
+//! Tooling around the daily solvers, run from anywhere in the repository:
+//!
+//! - `aoc report [--root DIR] [--days dec-01,dec-05] [--timeout SECS] [--format md|html]
+//!   [--out FILE] [--grids] [--no-tests]`: run every day and write a summary report.
+//! - `aoc submit DAY PART ANSWER [--root DIR] [--server ADDR]`: submit an answer, e.g.
+//!   `aoc submit dec-05 2 4719`, unless `DAY/notes.md` shows it is wrong. Wrong answers are
+//!   added to the notes.
+//! - `aoc serve --answers FILE [--addr ADDR] [--cooldown SECS]`: local stand-in for the answer
+//!   endpoint, judging against a `day part answer` fixture file.
+mod days;
+mod notes;
+mod report;
+mod server;
+mod submit;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use report::{Format, Options};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(|s| s.as_str()) {
+        Some("report") => cmd_report(&args[1..]),
+        Some("submit") => cmd_submit(&args[1..]),
+        Some("serve") => cmd_serve(&args[1..]),
+        _ => Err("usage: aoc report|submit|serve [options]".to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Address of the local stand-in server, see `aoc serve`.
+const DEFAULT_SERVER: &str = "127.0.0.1:7878";
+
+/// Default repository root: the parent of this crate.
+fn default_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..")
+}
+
+fn cmd_report(args: &[String]) -> Result<(), String> {
+    let mut root = default_root();
+    let mut only_days: Option<Vec<String>> = None;
+    let mut format = Format::Markdown;
+    let mut out_file = None;
+    let mut opts = Options {
+        timeout: Duration::from_secs(60),
+        tests: true,
+        grids: false,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--root" => root = PathBuf::from(value()?),
+            "--days" => only_days = Some(value()?.split(',').map(|d| d.to_string()).collect()),
+            "--timeout" => {
+                let secs: f64 = value()?
+                    .parse()
+                    .map_err(|_| "--timeout needs a number".to_string())?;
+                opts.timeout = Duration::from_secs_f64(secs);
+            }
+            "--format" => {
+                format = match value()?.as_str() {
+                    "md" | "markdown" => Format::Markdown,
+                    "html" => Format::Html,
+                    other => return Err(format!("unknown format: {}", other)),
+                }
+            }
+            "--out" => out_file = Some(PathBuf::from(value()?)),
+            "--grids" => opts.grids = true,
+            "--no-tests" => opts.tests = false,
+            other => return Err(format!("unknown option: {}", other)),
+        }
+    }
+
+    let mut day_list =
+        days::find_days(&root).map_err(|e| format!("cannot read {}: {}", root.display(), e))?;
+    if let Some(only) = only_days {
+        day_list.retain(|d| only.contains(&d.name));
+    }
+
+    let mut reports = Vec::new();
+    for day in &day_list {
+        eprintln!("Running {}...", day.name);
+        let rep = report::report_day(day, &opts).map_err(|e| format!("{}: {}", day.name, e))?;
+        reports.push(rep);
+    }
+
+    let doc = report::render(&reports, &opts, format);
+    match out_file {
+        Some(path) => std::fs::write(&path, doc)
+            .map_err(|e| format!("cannot write {}: {}", path.display(), e)),
+        None => {
+            print!("{}", doc);
+            Ok(())
+        }
+    }
+}
+
+fn cmd_submit(args: &[String]) -> Result<(), String> {
+    let mut root = default_root();
+    let mut server = DEFAULT_SERVER.to_string();
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--root" => root = PathBuf::from(value()?),
+            "--server" => server = value()?.clone(),
+            _ => positional.push(arg),
+        }
+    }
+    let [day, part, answer] = positional[..] else {
+        return Err("usage: aoc submit DAY PART ANSWER".to_string());
+    };
+    let part: u32 = part
+        .parse()
+        .map_err(|_| "PART must be 1 or 2".to_string())?;
+    let answer: u64 = answer
+        .parse()
+        .map_err(|_| "ANSWER must be a number".to_string())?;
+
+    let response = submit::submit(&root.join(day), &server, part, answer)?;
+    println!("{}", response);
+    Ok(())
+}
+
+fn cmd_serve(args: &[String]) -> Result<(), String> {
+    let mut addr = DEFAULT_SERVER.to_string();
+    let mut answers_file = None;
+    let mut cooldown = Duration::from_secs(60);
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--addr" => addr = value()?.clone(),
+            "--answers" => answers_file = Some(PathBuf::from(value()?)),
+            "--cooldown" => {
+                let secs: f64 = value()?
+                    .parse()
+                    .map_err(|_| "--cooldown needs a number".to_string())?;
+                cooldown = Duration::from_secs_f64(secs);
+            }
+            other => return Err(format!("unknown option: {}", other)),
+        }
+    }
+    let answers_file = answers_file.ok_or("usage: aoc serve --answers FILE".to_string())?;
+    let fixture = std::fs::read_to_string(&answers_file)
+        .map_err(|e| format!("cannot read {}: {}", answers_file.display(), e))?;
+    let answers = server::Server::parse_answers(&fixture)?;
+
+    let listener = std::net::TcpListener::bind(&addr)
+        .map_err(|e| format!("cannot listen on {}: {}", addr, e))?;
+    eprintln!("Serving {} answers on {}", answers.len(), addr);
+    server::Server::new(answers, cooldown)
+        .serve(listener)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,248 @@
+//! Read the answers we already know to be wrong from a day's `notes.md`.
+//!
+//! The notes look like this, one section per part and one wrong answer per line:
+//!
+//! ```text
+//! # Part 2:
+//!
+//! - 816194: too low
+//! - 8518174061514 is too high
+//! ```
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    TooLow,
+    TooHigh,
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Verdict::TooLow => write!(f, "too low"),
+            Verdict::TooHigh => write!(f, "too high"),
+        }
+    }
+}
+
+/// One answer that was rejected for a part.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Note {
+    pub part: u32,
+    pub value: u64,
+    pub verdict: Verdict,
+}
+
+/// Part number of a `# Part 2:` style header line.
+fn part_header(line: &str) -> Option<u32> {
+    let header = line.trim().strip_prefix('#')?.trim().to_lowercase();
+    header
+        .strip_prefix("part")?
+        .trim()
+        .trim_end_matches(':')
+        .parse()
+        .ok()
+}
+
+/// Parse the notes, lines that are not a recognised wrong answer are ignored.
+pub fn parse_notes(s: &str) -> Vec<Note> {
+    let mut notes = Vec::new();
+    let mut part = 1;
+    for line in s.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            part = part_header(line).unwrap_or(part);
+            continue;
+        }
+
+        let Some(entry) = line.strip_prefix('-') else {
+            continue;
+        };
+        let Some(value) = entry
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|w| !w.is_empty())
+            .and_then(|w| w.parse().ok())
+        else {
+            continue;
+        };
+        let verdict = if entry.contains("too low") {
+            Verdict::TooLow
+        } else if entry.contains("too high") {
+            Verdict::TooHigh
+        } else {
+            continue;
+        };
+        notes.push(Note {
+            part,
+            value,
+            verdict,
+        });
+    }
+    notes
+}
+
+/// Notes of a day directory, empty if it has no `notes.md`.
+pub fn read_notes(day_dir: &Path) -> Vec<Note> {
+    std::fs::read_to_string(day_dir.join("notes.md"))
+        .map(|s| parse_notes(&s))
+        .unwrap_or_default()
+}
+
+/// Add a wrong answer to the notes, at the end of its part's section or in a new section.
+pub fn add_note(s: &str, note: Note) -> String {
+    let entry = format!("- {} is {}", note.value, note.verdict);
+    let mut lines: Vec<&str> = s.lines().collect();
+
+    match lines.iter().position(|l| part_header(l) == Some(note.part)) {
+        Some(header) => {
+            let end = lines[header + 1..]
+                .iter()
+                .position(|l| l.trim().starts_with('#'))
+                .map_or(lines.len(), |ind| ind + header + 1);
+            // right after the last entry of the section, or after the header if it has none
+            let last = lines[header + 1..end]
+                .iter()
+                .rposition(|l| !l.trim().is_empty());
+            match last {
+                Some(ind) => lines.insert(header + 1 + ind + 1, &entry),
+                None => {
+                    lines.insert(header + 1, "");
+                    lines.insert(header + 2, &entry);
+                }
+            }
+            lines.join("\n") + "\n"
+        }
+        None => {
+            let mut out = s.trim_end().to_string();
+            if !out.is_empty() {
+                out.push_str("\n\n");
+            }
+            out + &format!("# Part {}\n\n{}\n", note.part, entry)
+        }
+    }
+}
+
+/// Add a wrong answer to a day directory's `notes.md`, the file is created if needed.
+pub fn record_note(day_dir: &Path, note: Note) -> std::io::Result<()> {
+    let path = day_dir.join("notes.md");
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    std::fs::write(path, add_note(&content, note))
+}
+
+/// Range the right answer of a part must lie in: above `above` and below `below`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Bounds {
+    pub above: Option<u64>,
+    pub below: Option<u64>,
+}
+
+impl Bounds {
+    /// Tightest bounds from all notes of the given part.
+    pub fn from_notes(notes: &[Note], part: u32) -> Bounds {
+        let mut bounds = Bounds::default();
+        for note in notes.iter().filter(|n| n.part == part) {
+            match note.verdict {
+                Verdict::TooLow => bounds.above = bounds.above.max(Some(note.value)),
+                Verdict::TooHigh => {
+                    bounds.below = Some(bounds.below.map_or(note.value, |b| b.min(note.value)))
+                }
+            }
+        }
+        bounds
+    }
+
+    pub fn is_known(&self) -> bool {
+        self.above.is_some() || self.below.is_some()
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        self.above.is_none_or(|a| value > a) && self.below.is_none_or(|b| value < b)
+    }
+}
+
+impl std::fmt::Display for Bounds {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match (self.above, self.below) {
+            (Some(a), Some(b)) => write!(f, "{} < x < {}", a, b),
+            (Some(a), None) => write!(f, "x > {}", a),
+            (None, Some(b)) => write!(f, "x < {}", b),
+            (None, None) => write!(f, "-"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTES: &str = "# Part 1:
+
+- 225367740: too high
+
+# Part 2
+
+- 816194: too low
+- 837564: too low
+- 840304: too low - missing inside areas that are connected to each other...
+- 8518174061514 is too high
+- 6363703648330 is too high as well
+";
+
+    #[test]
+    fn test_parse_notes() {
+        let notes = parse_notes(NOTES);
+        assert_eq!(notes.len(), 6);
+        assert_eq!(
+            notes[0],
+            Note {
+                part: 1,
+                value: 225367740,
+                verdict: Verdict::TooHigh
+            }
+        );
+        assert_eq!(notes[3].part, 2);
+        assert_eq!(notes[3].value, 840304);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let notes = parse_notes(NOTES);
+        let bounds = Bounds::from_notes(&notes, 2);
+        assert_eq!(bounds.above, Some(840304));
+        assert_eq!(bounds.below, Some(6363703648330));
+        assert!(!bounds.contains(840304));
+        assert!(bounds.contains(853588));
+        assert!(!Bounds::from_notes(&notes, 3).is_known());
+    }
+
+    #[test]
+    fn test_add_note() {
+        let note = |part, value| Note {
+            part,
+            value,
+            verdict: Verdict::TooLow,
+        };
+        let s = "# Part 1\n\n- 2649 is too low\n\n# Part 2 \n\n- 2832 is too high\n";
+        assert_eq!(
+            add_note(s, note(1, 2650)),
+            "# Part 1\n\n- 2649 is too low\n- 2650 is too low\n\n# Part 2 \n\n- 2832 is too high\n"
+        );
+        assert_eq!(
+            add_note(s, note(2, 10)),
+            "# Part 1\n\n- 2649 is too low\n\n# Part 2 \n\n- 2832 is too high\n- 10 is too low\n"
+        );
+        assert_eq!(
+            add_note("# Part 2:\n", note(2, 10)),
+            "# Part 2:\n\n- 10 is too low\n"
+        );
+        assert_eq!(add_note("", note(1, 10)), "# Part 1\n\n- 10 is too low\n");
+        assert_eq!(
+            add_note("# Part 1\n", note(2, 10)),
+            "# Part 1\n\n# Part 2\n\n- 10 is too low\n"
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! `aoc report`: run every day and summarise answers, timings, tests and known bounds.
+use std::time::Duration;
+
+use crate::days::{self, Day, Status, TestCounts};
+use crate::notes::{self, Bounds};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+/// Settings of a report run.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub timeout: Duration,
+    pub tests: bool,
+    pub grids: bool,
+}
+
+/// Everything we found out about one day.
+#[derive(Debug)]
+pub struct DayReport {
+    pub name: String,
+    pub status: Status,
+    pub elapsed: Duration,
+    pub answers: Vec<Answer>,
+    pub tests: Option<TestCounts>,
+    pub grid: Option<String>,
+}
+
+/// One `label: value` line printed by a solver, with the bounds known for its part.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Answer {
+    pub label: String,
+    pub value: u64,
+    pub part: u32,
+    pub bounds: Bounds,
+}
+
+impl Answer {
+    fn bounds_ok(&self) -> bool {
+        self.bounds.contains(self.value)
+    }
+}
+
+/// Build, run and test a single day.
+pub fn report_day(day: &Day, opts: &Options) -> std::io::Result<DayReport> {
+    let mut report = DayReport {
+        name: day.name.clone(),
+        status: Status::Failed(None),
+        elapsed: Duration::ZERO,
+        answers: Vec::new(),
+        tests: None,
+        grid: None,
+    };
+    if !day.build()? {
+        return Ok(report);
+    }
+
+    let grid_day = opts.grids && days::GRID_DAYS.contains(&day.name.as_str());
+    let args: &[&str] = if grid_day { &["--grid"] } else { &[] };
+    let out = day.run(args, opts.timeout)?;
+    report.status = out.status;
+    report.elapsed = out.elapsed;
+
+    let notes = notes::read_notes(&day.dir);
+    report.answers = parse_answers(&out.stdout)
+        .into_iter()
+        .map(|(label, value, part)| Answer {
+            label,
+            value,
+            part,
+            bounds: Bounds::from_notes(&notes, part),
+        })
+        .collect();
+    if grid_day {
+        report.grid = last_grid(&out.stdout);
+    }
+    if opts.tests {
+        report.tests = day.test(opts.timeout)?;
+    }
+    Ok(report)
+}
+
+/// Find the answers in a solver's output: lines like `Part 1: 123` or `Distance: 42`.
+///
+/// The part is taken from the label (`part 2`, `Q2`) if it names one, otherwise an answer
+/// belongs to the part after the previous answer. If the output names its parts, unnamed lines
+/// before the first named one are debug output and skipped.
+pub fn parse_answers(stdout: &str) -> Vec<(String, u64, u32)> {
+    let names_parts = stdout
+        .lines()
+        .filter_map(|line| line.rsplit_once(':'))
+        .any(|(label, _)| part_from_label(label).is_some());
+
+    let mut answers: Vec<(String, u64, u32)> = Vec::new();
+    for line in stdout.lines() {
+        let Some((label, value)) = line.rsplit_once(':') else {
+            continue;
+        };
+        // debug output like `# robots: 12` is no answer
+        if label.starts_with('#') {
+            continue;
+        }
+        let Ok(value) = value.trim().parse::<u64>() else {
+            continue;
+        };
+        let part = match (part_from_label(label), answers.last()) {
+            (Some(part), _) => part,
+            (None, Some(prev)) => prev.2 + 1,
+            (None, None) if names_parts => continue,
+            (None, None) => 1,
+        };
+        answers.push((label.trim().to_string(), value, part));
+    }
+    answers
+}
+
+fn part_from_label(label: &str) -> Option<u32> {
+    let label = label.to_lowercase();
+    for key in ["part", "q"] {
+        if let Some(ind) = label.find(key) {
+            let rest = label[ind + key.len()..].trim_start();
+            let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            if let Ok(part) = digits.parse() {
+                return Some(part);
+            }
+        }
+    }
+    None
+}
+
+/// The last block of at least three equally long lines without spaces, i.e., a printed grid.
+pub fn last_grid(stdout: &str) -> Option<String> {
+    let is_grid_line =
+        |l: &str| !l.is_empty() && !l.contains(char::is_whitespace) && !l.contains(':');
+
+    let mut last = None;
+    let mut block: Vec<&str> = Vec::new();
+    for line in stdout.lines().chain(std::iter::once("")) {
+        if is_grid_line(line) && block.first().is_none_or(|first| first.len() == line.len()) {
+            block.push(line);
+            continue;
+        }
+        if block.len() >= 3 {
+            last = Some(block.join("\n"));
+        }
+        block.clear();
+        if is_grid_line(line) {
+            block.push(line);
+        }
+    }
+    last
+}
+
+/// Render all day reports into one document.
+pub fn render(reports: &[DayReport], opts: &Options, format: Format) -> String {
+    match format {
+        Format::Markdown => render_markdown(reports, opts),
+        Format::Html => render_html(reports, opts),
+    }
+}
+
+fn tests_cell(report: &DayReport, opts: &Options) -> String {
+    match report.tests {
+        _ if !opts.tests => "skipped".to_string(),
+        Some(t) if t.failed == 0 => format!("{} passed", t.passed),
+        Some(t) => format!("{} passed, {} FAILED", t.passed, t.failed),
+        None => "did not run".to_string(),
+    }
+}
+
+fn bounds_cell(report: &DayReport) -> String {
+    let checks: Vec<String> = report
+        .answers
+        .iter()
+        .filter(|a| a.bounds.is_known())
+        .map(|a| {
+            let verdict = if a.bounds_ok() { "ok" } else { "OUT OF BOUNDS" };
+            format!("part {}: {} ({})", a.part, verdict, a.bounds)
+        })
+        .collect();
+    if checks.is_empty() {
+        "-".to_string()
+    } else {
+        checks.join(", ")
+    }
+}
+
+fn answers_list(report: &DayReport) -> Vec<String> {
+    report
+        .answers
+        .iter()
+        .map(|a| format!("{}: {}", a.label, a.value))
+        .collect()
+}
+
+fn render_markdown(reports: &[DayReport], opts: &Options) -> String {
+    let mut out = String::from("# Advent of Code 2024 report\n\n");
+    out.push_str(&format!(
+        "Timeout per day: {} s\n\n",
+        opts.timeout.as_secs_f64()
+    ));
+    out.push_str("| Day | Status | Time (s) | Answers | Tests | Known bounds |\n");
+    out.push_str("|---|---|---|---|---|---|\n");
+    for r in reports {
+        out.push_str(&format!(
+            "| {} | {} | {:.3} | {} | {} | {} |\n",
+            r.name,
+            r.status,
+            r.elapsed.as_secs_f64(),
+            answers_list(r).join("<br>"),
+            tests_cell(r, opts),
+            bounds_cell(r)
+        ));
+    }
+
+    for r in reports {
+        if let Some(grid) = &r.grid {
+            out.push_str(&format!(
+                "\n## {} final grid\n\n```\n{}\n```\n",
+                r.name, grid
+            ));
+        }
+    }
+    out
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn render_html(reports: &[DayReport], opts: &Options) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Advent of Code 2024 report</title></head>\n<body>\n",
+    );
+    out.push_str("<h1>Advent of Code 2024 report</h1>\n");
+    out.push_str(&format!(
+        "<p>Timeout per day: {} s</p>\n",
+        opts.timeout.as_secs_f64()
+    ));
+    out.push_str("<table>\n<tr><th>Day</th><th>Status</th><th>Time (s)</th><th>Answers</th><th>Tests</th><th>Known bounds</th></tr>\n");
+    for r in reports {
+        let answers: Vec<String> = answers_list(r).iter().map(|a| escape_html(a)).collect();
+        out.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{:.3}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&r.name),
+            r.status,
+            r.elapsed.as_secs_f64(),
+            answers.join("<br>"),
+            tests_cell(r, opts),
+            escape_html(&bounds_cell(r))
+        ));
+    }
+    out.push_str("</table>\n");
+
+    for r in reports {
+        if let Some(grid) = &r.grid {
+            out.push_str(&format!(
+                "<h2>{} final grid</h2>\n<pre>{}</pre>\n",
+                escape_html(&r.name),
+                escape_html(grid)
+            ));
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_answers() {
+        let out = "# robots: 137\nResult Q1: 168539636\nTotal safe reports: 257\nPart 2 stopped (time limit) after 35 blinks: 12989024 stones so far\n";
+        let answers = parse_answers(out);
+        assert_eq!(
+            answers,
+            vec![
+                ("Result Q1".to_string(), 168539636, 1),
+                ("Total safe reports".to_string(), 257, 2),
+            ]
+        );
+        assert_eq!(parse_answers("Price part 2: 840304")[0].2, 2);
+
+        let out = "Quadrant: 0\nxmin 0 ymin 0\nPart 1: 12\n.#.\nSteps: 6512\n";
+        assert_eq!(
+            parse_answers(out),
+            vec![
+                ("Part 1".to_string(), 12, 1),
+                ("Steps".to_string(), 6512, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_last_grid() {
+        let out = "Part 1: 3\n#..\n.#.\n..#\nSteps: 2\n##\n..\n##\n..\nTotal: 4\n";
+        assert_eq!(last_grid(out), Some("##\n..\n##\n..".to_string()));
+        assert_eq!(last_grid("Part 1: 3\n#..\n.#.\n"), None);
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let opts = Options {
+            timeout: Duration::from_secs(5),
+            tests: true,
+            grids: true,
+        };
+        let report = DayReport {
+            name: "dec-12".to_string(),
+            status: Status::Finished,
+            elapsed: Duration::from_millis(1500),
+            answers: vec![Answer {
+                label: "Price part 2".to_string(),
+                value: 840304,
+                part: 2,
+                bounds: Bounds {
+                    above: Some(840304),
+                    below: None,
+                },
+            }],
+            tests: Some(TestCounts {
+                passed: 8,
+                failed: 0,
+            }),
+            grid: Some("AA\nAB".to_string()),
+        };
+        let md = render(&[report], &opts, Format::Markdown);
+        assert!(md.contains(
+            "| dec-12 | ok | 1.500 | Price part 2: 840304 | 8 passed | part 2: OUT OF BOUNDS (x > 840304) |"
+        ));
+        assert!(md.contains("## dec-12 final grid\n\n```\nAA\nAB\n```"));
+    }
+}
@@ -0,0 +1,231 @@
+//! `aoc serve`: local stand-in for the puzzle site's answer endpoint, so submitting can be
+//! exercised offline.
+//!
+//! Answers are posted like on the real site, `POST /2024/day/{day}/answer` with the form body
+//! `level={part}&answer={answer}`. The plain-text reply is `correct`, `too high`, `too low` or
+//! `wait {seconds}` while the cooldown after a wrong answer is running. A day and part without a
+//! known answer is `404 Not Found`, like a puzzle that does not exist.
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use crate::submit::Response;
+
+/// Largest request body read, the `level=..&answer=..` form is far smaller.
+const MAX_BODY: usize = 1024;
+
+pub struct Server {
+    answers: HashMap<(u32, u32), u64>,
+    cooldown: Duration,
+    blocked_until: Option<Instant>,
+}
+
+impl Server {
+    pub fn new(answers: HashMap<(u32, u32), u64>, cooldown: Duration) -> Server {
+        Server {
+            answers,
+            cooldown,
+            blocked_until: None,
+        }
+    }
+
+    /// Read the right answers from a fixture with `day part answer` lines, `#` starts a comment.
+    pub fn parse_answers(s: &str) -> Result<HashMap<(u32, u32), u64>, String> {
+        let mut answers = HashMap::new();
+        for (lt, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let parsed = match parts[..] {
+                [day, part, answer] => day
+                    .parse()
+                    .ok()
+                    .zip(part.parse().ok())
+                    .zip(answer.parse().ok()),
+                _ => None,
+            };
+            let Some(((day, part), answer)) = parsed else {
+                return Err(format!("line {}: expected `day part answer`", lt + 1));
+            };
+            answers.insert((day, part), answer);
+        }
+        Ok(answers)
+    }
+
+    /// Judge one submitted answer, `None` if there is no answer for that day and part.
+    pub fn respond(&mut self, day: u32, part: u32, answer: u64) -> Option<Response> {
+        let right = *self.answers.get(&(day, part))?;
+        let now = Instant::now();
+        if let Some(until) = self.blocked_until.filter(|until| *until > now) {
+            return Some(Response::Wait(until - now));
+        }
+
+        let response = if answer == right {
+            Response::Correct
+        } else if answer > right {
+            Response::TooHigh
+        } else {
+            Response::TooLow
+        };
+        if response != Response::Correct {
+            self.blocked_until = Some(now + self.cooldown);
+        }
+        Some(response)
+    }
+
+    /// Answer requests one after the other, forever.
+    pub fn serve(&mut self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            // a broken client must not take the server down
+            if let Err(e) = self.handle(stream?) {
+                eprintln!("request failed: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, mut stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header)?;
+            let header = header.trim();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((key, value)) = header.split_once(':') {
+                if key.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        if content_length > MAX_BODY {
+            return reply(&mut stream, "413 Payload Too Large", "request too large");
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        let (status, text) = match parse_request(&request_line, &String::from_utf8_lossy(&body)) {
+            Some((day, part, answer)) => match self.respond(day, part, answer) {
+                Some(response) => ("200 OK", response.to_string()),
+                None => ("404 Not Found", "no such puzzle".to_string()),
+            },
+            None => ("400 Bad Request", "bad request".to_string()),
+        };
+        reply(&mut stream, status, &text)
+    }
+}
+
+fn reply(stream: &mut TcpStream, status: &str, text: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        text.len(),
+        text
+    )
+}
+
+/// Day, part and answer of a `POST /2024/day/{day}/answer` request with a `level=..&answer=..` body.
+fn parse_request(request_line: &str, body: &str) -> Option<(u32, u32, u64)> {
+    let mut words = request_line.split_whitespace();
+    if words.next()? != "POST" {
+        return None;
+    }
+    let day = words
+        .next()?
+        .strip_prefix("/2024/day/")?
+        .strip_suffix("/answer")?
+        .parse()
+        .ok()?;
+
+    let mut part = None;
+    let mut answer = None;
+    for field in body.trim().split('&') {
+        match field.split_once('=') {
+            Some(("level", v)) => part = v.parse().ok(),
+            Some(("answer", v)) => answer = v.parse().ok(),
+            _ => (),
+        }
+    }
+    Some((day, part?, answer?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_answers() {
+        let answers =
+            Server::parse_answers("# day part answer\n5 1 143\n5 2 123 # example\n").unwrap();
+        assert_eq!(answers.get(&(5, 2)), Some(&123));
+        assert!(Server::parse_answers("5 1").is_err());
+    }
+
+    #[test]
+    fn test_respond_with_cooldown() {
+        let answers = HashMap::from([((5, 1), 143)]);
+        let mut server = Server::new(answers, Duration::from_secs(60));
+        assert_eq!(server.respond(5, 1, 143), Some(Response::Correct));
+        assert_eq!(server.respond(5, 1, 200), Some(Response::TooHigh));
+        assert!(matches!(server.respond(5, 1, 143), Some(Response::Wait(_))));
+
+        let mut server = Server::new(HashMap::from([((5, 1), 143)]), Duration::ZERO);
+        assert_eq!(server.respond(5, 1, 100), Some(Response::TooLow));
+        assert_eq!(server.respond(5, 1, 143), Some(Response::Correct));
+    }
+
+    #[test]
+    fn test_respond_unknown_puzzle() {
+        let mut server = Server::new(HashMap::from([((5, 1), 143)]), Duration::from_secs(60));
+        assert_eq!(server.respond(5, 2, 100), None);
+        assert_eq!(server.respond(6, 1, 100), None);
+        // no cooldown was started by them
+        assert_eq!(server.respond(5, 1, 143), Some(Response::Correct));
+    }
+
+    #[test]
+    fn test_body_too_large() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut server = Server::new(HashMap::new(), Duration::ZERO);
+        std::thread::spawn(move || server.serve(listener));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "POST /2024/day/5/answer HTTP/1.1\r\nContent-Length: 18446744073709551615\r\n\r\n"
+        )
+        .unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+        assert!(reply.starts_with("HTTP/1.1 413"));
+    }
+
+    #[test]
+    fn test_parse_request() {
+        assert_eq!(
+            parse_request(
+                "POST /2024/day/12/answer HTTP/1.1\r\n",
+                "level=2&answer=840304"
+            ),
+            Some((12, 2, 840304))
+        );
+        assert_eq!(
+            parse_request("GET /2024/day/12/answer HTTP/1.1", "level=2&answer=1"),
+            None
+        );
+        assert_eq!(
+            parse_request("POST /2024/day/12/answer HTTP/1.1", "level=2"),
+            None
+        );
+    }
+}
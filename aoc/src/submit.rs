@@ -0,0 +1,207 @@
+//! `aoc submit`: post an answer to the answer endpoint, unless `notes.md` already rules it out,
+//! and keep track of wrong answers in `notes.md`.
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::notes::{self, Bounds, Note, Verdict};
+
+/// What the answer endpoint said about a submission.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Response {
+    Correct,
+    TooHigh,
+    TooLow,
+    Wait(Duration),
+}
+
+impl Response {
+    /// Parse the plain-text reply of the endpoint.
+    pub fn parse(s: &str) -> Option<Response> {
+        match s.trim() {
+            "correct" => Some(Response::Correct),
+            "too high" => Some(Response::TooHigh),
+            "too low" => Some(Response::TooLow),
+            other => {
+                let secs = other.strip_prefix("wait ")?.parse().ok()?;
+                Some(Response::Wait(Duration::from_secs(secs)))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Response {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Response::Correct => write!(f, "correct"),
+            Response::TooHigh => write!(f, "too high"),
+            Response::TooLow => write!(f, "too low"),
+            // round up, so waiting the given time is always enough
+            Response::Wait(d) => write!(f, "wait {}", d.as_secs() + (d.subsec_nanos() > 0) as u64),
+        }
+    }
+}
+
+/// Day number from a day directory name, e.g. 11 for `dec-11_p2`.
+pub fn day_number(name: &str) -> Option<u32> {
+    let digits: String = name
+        .strip_prefix("dec-")?
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// Refuse an answer that the notes already show to be wrong.
+pub fn check_notes(notes: &[Note], part: u32, answer: u64) -> Result<(), String> {
+    let bounds = Bounds::from_notes(notes, part);
+    if bounds.contains(answer) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} is known to be wrong for part {}, the answer must be {}",
+            answer, part, bounds
+        ))
+    }
+}
+
+/// Post an answer to the endpoint at `addr` (e.g. `127.0.0.1:7878`).
+pub fn post_answer(addr: &str, day: u32, part: u32, answer: u64) -> io::Result<Response> {
+    let mut stream = TcpStream::connect(addr)?;
+    let body = format!("level={}&answer={}", part, answer);
+    write!(
+        stream,
+        "POST /2024/day/{}/answer HTTP/1.1\r\nHost: {}\r\nContent-Type: application/x-www-form-urlencoded\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        day,
+        addr,
+        body.len(),
+        body
+    )?;
+
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+    let invalid = |what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", what, reply.trim()),
+        )
+    };
+    let (head, text) = reply
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| invalid("malformed reply"))?;
+    if !head.starts_with("HTTP/1.1 200") {
+        return Err(invalid("request rejected"));
+    }
+    Response::parse(text).ok_or_else(|| invalid("unknown reply"))
+}
+
+/// Submit an answer for the day in `day_dir`: check the notes first, post it, and add it to the
+/// notes if it was too high or too low.
+pub fn submit(day_dir: &Path, addr: &str, part: u32, answer: u64) -> Result<Response, String> {
+    let name = day_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let day = day_number(&name).ok_or(format!("{} is not a day directory", day_dir.display()))?;
+    check_notes(&notes::read_notes(day_dir), part, answer)?;
+
+    let response =
+        post_answer(addr, day, part, answer).map_err(|e| format!("submitting failed: {}", e))?;
+    let verdict = match response {
+        Response::TooLow => Some(Verdict::TooLow),
+        Response::TooHigh => Some(Verdict::TooHigh),
+        _ => None,
+    };
+    if let Some(verdict) = verdict {
+        let note = Note {
+            part,
+            value: answer,
+            verdict,
+        };
+        notes::record_note(day_dir, note).map_err(|e| format!("cannot update notes: {}", e))?;
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::Server;
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_response_roundtrip() {
+        for resp in [
+            Response::Correct,
+            Response::TooHigh,
+            Response::TooLow,
+            Response::Wait(Duration::from_secs(42)),
+        ] {
+            assert_eq!(Response::parse(&resp.to_string()), Some(resp));
+        }
+        assert_eq!(
+            Response::Wait(Duration::from_millis(1500)).to_string(),
+            "wait 2"
+        );
+        assert_eq!(Response::parse("maybe"), None);
+    }
+
+    #[test]
+    fn test_day_number() {
+        assert_eq!(day_number("dec-05"), Some(5));
+        assert_eq!(day_number("dec-11_p2"), Some(11));
+        assert_eq!(day_number("aoc"), None);
+    }
+
+    #[test]
+    fn test_check_notes() {
+        let notes = notes::parse_notes("# Part 1\n\n- 2649 is too low\n");
+        assert!(check_notes(&notes, 1, 2649).is_err());
+        assert!(check_notes(&notes, 1, 2654).is_ok());
+        assert!(check_notes(&notes, 2, 1).is_ok());
+    }
+
+    #[test]
+    fn test_submit_against_local_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let mut server = Server::new(HashMap::from([((5, 1), 143)]), Duration::ZERO);
+        std::thread::spawn(move || server.serve(listener));
+
+        let day_dir = std::env::temp_dir()
+            .join(format!("aoc-submit-{}", std::process::id()))
+            .join("dec-05");
+        std::fs::create_dir_all(&day_dir).unwrap();
+
+        assert_eq!(submit(&day_dir, &addr, 1, 100), Ok(Response::TooLow));
+        assert_eq!(submit(&day_dir, &addr, 1, 200), Ok(Response::TooHigh));
+        // known to be wrong from the notes, not sent again
+        assert!(submit(&day_dir, &addr, 1, 90).is_err());
+        assert_eq!(submit(&day_dir, &addr, 1, 143), Ok(Response::Correct));
+
+        let notes = std::fs::read_to_string(day_dir.join("notes.md")).unwrap();
+        std::fs::remove_dir_all(day_dir.parent().unwrap()).unwrap();
+        assert_eq!(notes, "# Part 1\n\n- 100 is too low\n- 200 is too high\n");
+    }
+
+    #[test]
+    fn test_submit_unknown_puzzle() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let mut server = Server::new(HashMap::from([((5, 1), 143)]), Duration::ZERO);
+        std::thread::spawn(move || server.serve(listener));
+
+        let day_dir = std::env::temp_dir()
+            .join(format!("aoc-unknown-{}", std::process::id()))
+            .join("dec-06");
+        std::fs::create_dir_all(&day_dir).unwrap();
+
+        let result = submit(&day_dir, &addr, 1, 100);
+        let noted = day_dir.join("notes.md").exists();
+        std::fs::remove_dir_all(day_dir.parent().unwrap()).unwrap();
+        assert!(result.unwrap_err().contains("404"));
+        assert!(!noted);
+    }
+}
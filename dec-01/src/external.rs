@@ -0,0 +1,328 @@
+//! Streaming mode for lists that do not fit into memory.
+//!
+//! The input is read in chunks of `chunk_size` lines. Each chunk's columns are sorted and spilled
+//! to temporary run files, which are merged again when a distance or similarity is computed. The
+//! similarity counts equal values while walking two merged columns side by side, so memory use
+//! does not grow with the number of distinct values.
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::Metric;
+
+/// Sorted runs of every column, kept in a temporary directory that is removed on drop.
+pub struct SpilledLists {
+    dir: PathBuf,
+    /// Run files of each column.
+    runs: Vec<Vec<PathBuf>>,
+    /// Number of entries of each column.
+    lens: Vec<u64>,
+}
+
+impl SpilledLists {
+    /// Read whitespace separated columns like `LocationLists::from_text`, with at most
+    /// `chunk_size` lines in memory at a time.
+    pub fn from_reader(reader: impl BufRead, chunk_size: usize) -> Result<SpilledLists, String> {
+        let dir =
+            std::env::temp_dir().join(format!("dec-01-runs-{}-{}", std::process::id(), unique()));
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+        let mut lists = SpilledLists {
+            dir,
+            runs: vec![],
+            lens: vec![],
+        };
+
+        let mut chunk: Vec<Vec<u64>> = vec![];
+        for (lt, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| format!("line {}: {}", lt + 1, e))?;
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.is_empty() {
+                continue;
+            }
+            if lists.lens.is_empty() {
+                let n = parts.len();
+                (lists.runs, lists.lens) = (vec![vec![]; n], vec![0; n]);
+                chunk = vec![vec![]; n];
+            }
+            if parts.len() != chunk.len() {
+                return Err(format!("line {}: expected {} columns", lt + 1, chunk.len()));
+            }
+            for (col, part) in parts.iter().enumerate() {
+                let val: u64 = part
+                    .parse()
+                    .map_err(|e| format!("line {}: {}", lt + 1, e))?;
+                chunk[col].push(val);
+                lists.lens[col] += 1;
+            }
+            if chunk[0].len() >= chunk_size {
+                lists.spill(&mut chunk)?;
+            }
+        }
+        if chunk.first().is_some_and(|c| !c.is_empty()) {
+            lists.spill(&mut chunk)?;
+        }
+        Ok(lists)
+    }
+
+    /// Sort the columns of a chunk and write each to a new run file.
+    fn spill(&mut self, chunk: &mut [Vec<u64>]) -> Result<(), String> {
+        for (col, vals) in chunk.iter_mut().enumerate() {
+            vals.sort_unstable();
+            let path = self
+                .dir
+                .join(format!("col{}-run{}", col, self.runs[col].len()));
+            write_run(&path, vals)
+                .map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+            self.runs[col].push(path);
+            vals.clear();
+        }
+        Ok(())
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.lens.len()
+    }
+
+    /// Number of entries without partner when pairing up columns `a` and `b`.
+    pub fn unpaired_len(&self, a: usize, b: usize) -> u64 {
+        self.lens[a].abs_diff(self.lens[b])
+    }
+
+    /// All entries of a column in sorted order, merged from its runs.
+    pub fn sorted(&self, col: usize) -> io::Result<MergedRuns> {
+        MergedRuns::open(&self.runs[col])
+    }
+
+    pub fn distance_between(&self, a: usize, b: usize, metric: Metric) -> io::Result<u128> {
+        let (left, right) = (self.sorted(a)?, self.sorted(b)?);
+        let mut error = None;
+        let pairs = left.zip(right).map_while(|pair| match pair {
+            (Ok(l), Ok(r)) => Some((l, r)),
+            (Err(e), _) | (_, Err(e)) => {
+                error = Some(e);
+                None
+            }
+        });
+        let distance = metric.distance(pairs);
+        match error {
+            Some(e) => Err(e),
+            None => Ok(distance),
+        }
+    }
+
+    /// Same as `LocationLists::similarity_between`, by a merge join of the two sorted columns.
+    pub fn similarity_between(
+        &self,
+        a: usize,
+        b: usize,
+        weight: impl Fn(u64, u64) -> u64,
+    ) -> io::Result<u64> {
+        let mut right = Runs::new(self.sorted(b)?);
+        let mut next_right = right.next().transpose()?;
+        let mut total = 0;
+        for run in Runs::new(self.sorted(a)?) {
+            let (val, occ) = run?;
+            // skip the values of `b` below the current one of `a`
+            while let Some((r, _)) = next_right {
+                if r >= val {
+                    break;
+                }
+                next_right = right.next().transpose()?;
+            }
+            let matched = match next_right {
+                Some((r, n)) if r == val => n,
+                _ => 0,
+            };
+            total += occ * weight(val, matched);
+        }
+        Ok(total)
+    }
+}
+
+impl Drop for SpilledLists {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Distinguishes the run directories of several `SpilledLists` in one process.
+fn unique() -> usize {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+fn write_run(path: &Path, vals: &[u64]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    for val in vals {
+        out.write_all(&val.to_le_bytes())?;
+    }
+    out.flush()
+}
+
+/// One sorted run file, read value by value.
+struct Run {
+    reader: BufReader<File>,
+}
+
+impl Run {
+    fn next_value(&mut self) -> io::Result<Option<u64>> {
+        let mut buf = [0; 8];
+        match self.reader.read_exact(&mut buf) {
+            Ok(()) => Ok(Some(u64::from_le_bytes(buf))),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// K-way merge of sorted run files.
+pub struct MergedRuns {
+    runs: Vec<Run>,
+    /// Next value of every run that is not exhausted yet, with the run's index.
+    heads: BinaryHeap<Reverse<(u64, usize)>>,
+}
+
+impl MergedRuns {
+    fn open(paths: &[PathBuf]) -> io::Result<MergedRuns> {
+        let mut merged = MergedRuns {
+            runs: vec![],
+            heads: BinaryHeap::new(),
+        };
+        for (ind, path) in paths.iter().enumerate() {
+            let mut run = Run {
+                reader: BufReader::new(File::open(path)?),
+            };
+            if let Some(val) = run.next_value()? {
+                merged.heads.push(Reverse((val, ind)));
+            }
+            merged.runs.push(run);
+        }
+        Ok(merged)
+    }
+}
+
+impl Iterator for MergedRuns {
+    type Item = io::Result<u64>;
+
+    fn next(&mut self) -> Option<io::Result<u64>> {
+        let Reverse((val, ind)) = self.heads.pop()?;
+        match self.runs[ind].next_value() {
+            Ok(Some(next)) => self.heads.push(Reverse((next, ind))),
+            Ok(None) => (),
+            Err(e) => return Some(Err(e)),
+        }
+        Some(Ok(val))
+    }
+}
+
+/// Equal values of a sorted stream, as `(value, occurrences)`.
+struct Runs {
+    values: MergedRuns,
+    /// First value of the next run, already read.
+    pending: Option<u64>,
+}
+
+impl Runs {
+    fn new(values: MergedRuns) -> Runs {
+        Runs {
+            values,
+            pending: None,
+        }
+    }
+}
+
+impl Iterator for Runs {
+    type Item = io::Result<(u64, u64)>;
+
+    fn next(&mut self) -> Option<io::Result<(u64, u64)>> {
+        let val = match self.pending.take() {
+            Some(val) => val,
+            None => match self.values.next()? {
+                Ok(val) => val,
+                Err(e) => return Some(Err(e)),
+            },
+        };
+        let mut occ = 1;
+        for next in self.values.by_ref() {
+            match next {
+                Ok(next) if next == val => occ += 1,
+                Ok(next) => {
+                    self.pending = Some(next);
+                    break;
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        Some(Ok((val, occ)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LocationLists;
+
+    #[test]
+    fn test_matches_in_memory() {
+        let data = std::fs::read_to_string("./src/input1").unwrap();
+        let lists = LocationLists::from_text(&data).unwrap();
+        // small chunks, so there are many runs to merge
+        let spilled = SpilledLists::from_reader(data.as_bytes(), 97).unwrap();
+        assert!(spilled.runs[0].len() > 1);
+
+        let sorted: Vec<u64> = spilled.sorted(1).unwrap().map(|v| v.unwrap()).collect();
+        assert_eq!(sorted, lists.columns[1]);
+        for metric in [Metric::Absolute, Metric::Squared, Metric::Chebyshev] {
+            assert_eq!(
+                spilled.distance_between(0, 1, metric).unwrap(),
+                lists.distance_between(0, 1, metric)
+            );
+        }
+        assert_eq!(
+            spilled
+                .similarity_between(0, 1, |val, occ| val * occ)
+                .unwrap(),
+            lists.similarity_between(0, 1, |val, occ| val * occ)
+        );
+        assert_eq!(spilled.unpaired_len(0, 1), 0);
+    }
+
+    #[test]
+    fn test_similarity_merge() {
+        let text = "3 4\n4 3\n2 5\n1 3\n3 9\n3 3\n7 7\n7 1\n";
+        let lists = LocationLists::from_text(text).unwrap();
+        for chunk_size in [1, 2, 3, 100] {
+            let spilled = SpilledLists::from_reader(text.as_bytes(), chunk_size).unwrap();
+            for (a, b) in [(0, 1), (1, 0)] {
+                assert_eq!(
+                    spilled
+                        .similarity_between(a, b, |val, occ| val * occ)
+                        .unwrap(),
+                    lists.similarity_between(a, b, |val, occ| val * occ)
+                );
+                assert_eq!(
+                    spilled.similarity_between(a, b, |_, occ| occ).unwrap(),
+                    lists.similarity_between(a, b, |_, occ| occ)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_runs_removed() {
+        let spilled = SpilledLists::from_reader("3 4\n4 3\n2 5\n".as_bytes(), 2).unwrap();
+        let dir = spilled.dir.clone();
+        assert!(dir.exists());
+        drop(spilled);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_bad_text() {
+        assert!(SpilledLists::from_reader("1 2\n3\n".as_bytes(), 10).is_err());
+    }
+}
@@ -1,42 +1,476 @@
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::collections::HashMap;
+
+mod external;
+
+use external::SpilledLists;
 
 fn main() {
-    // read the input file into two vectors
-    let mut col1: Vec<u64> = vec![];
-    let mut col2: Vec<u64> = vec![];
-    
-    let fname = "./src/input1";
-    let f = File::open(fname).expect("file not found");
-    let lines = io::BufReader::new(f).lines();
+    // Usage: dec-01 [FILE] [--metric absolute|squared|chebyshev] [--reference COLUMN]
+    //               [--stream [--chunk LINES]] [--diff [--top K]]
+    // Without `--reference`, every pair of columns is compared. `--stream` sorts the lists in
+    // chunks on disk instead of in memory, for inputs larger than RAM. `--diff` lists every sorted
+    // pair, the `K` (default 10) largest contributors and the values found in only one column; it
+    // cannot be combined with `--stream`.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut fname = "./src/input1".to_string();
+    let mut metric = Metric::Absolute;
+    let mut comparison = Comparison::Pairwise;
+    let mut stream = false;
+    let mut chunk_size = 1_000_000;
+    let mut diff_top = None;
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--metric" => {
+                let name = it.next().map_or("", |name| name.as_str());
+                metric = Metric::from_name(name).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                })
+            }
+            "--reference" => {
+                let col = it
+                    .next()
+                    .and_then(|c| c.parse().ok())
+                    .expect("--reference needs a column");
+                comparison = Comparison::ToReference(col);
+            }
+            "--stream" => stream = true,
+            "--diff" => diff_top = diff_top.or(Some(10)),
+            "--top" => {
+                diff_top = it.next().and_then(|k| k.parse().ok());
+                assert!(diff_top.is_some(), "--top needs a number of pairs");
+            }
+            "--chunk" => {
+                chunk_size = it
+                    .next()
+                    .and_then(|c| c.parse().ok())
+                    .expect("--chunk needs a number of lines")
+            }
+            _ => fname = arg.clone(),
+        }
+    }
+
+    if stream && diff_top.is_some() {
+        eprintln!("--diff and --top need the lists in memory and cannot be used with --stream");
+        std::process::exit(1);
+    }
+
+    if stream {
+        let file = std::fs::File::open(fname).expect("file not found");
+        let lists = SpilledLists::from_reader(std::io::BufReader::new(file), chunk_size).unwrap();
+        check_columns(comparison, lists.num_columns());
+        print_results(
+            lists.num_columns(),
+            comparison,
+            |a, b| lists.distance_between(a, b, metric).unwrap(),
+            |a, b| {
+                lists
+                    .similarity_between(a, b, |val, occ| val * occ)
+                    .unwrap()
+            },
+            |a, b| lists.unpaired_len(a, b),
+        );
+    } else {
+        // read the input file into one list per column
+        let data = std::fs::read_to_string(fname).expect("file not found");
+        let lists = LocationLists::from_text(&data).unwrap();
+        check_columns(comparison, lists.num_columns());
+        print_results(
+            lists.num_columns(),
+            comparison,
+            |a, b| lists.distance_between(a, b, metric),
+            |a, b| lists.similarity_between(a, b, |val, occ| val * occ),
+            |a, b| lists.unpaired(a, b).len() as u64,
+        );
+        if let Some(top) = diff_top {
+            for (a, b) in comparison.column_pairs(lists.num_columns()) {
+                println!();
+                print_diff(&lists, a, b, metric, top);
+            }
+        }
+    }
+}
+
+/// Exit with an error if the reference column is not one of the `num_columns` columns.
+fn check_columns(comparison: Comparison, num_columns: usize) {
+    if let Err(e) = comparison.check(num_columns) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Print the results for two lists in the puzzle's format.
+fn print_puzzle(distance: u128, unpaired_len: u64, similarity: u64) {
+    println!("Distance: {}", distance);
+    if unpaired_len > 0 {
+        println!("Entries without partner: {}", unpaired_len);
+    }
+    println!("Similarity score: {}", similarity);
+}
+
+/// Print distance and similarity of the compared columns, in the puzzle's format for two columns.
+fn print_results(
+    num_columns: usize,
+    comparison: Comparison,
+    distance: impl Fn(usize, usize) -> u128,
+    similarity: impl Fn(usize, usize) -> u64,
+    unpaired_len: impl Fn(usize, usize) -> u64,
+) {
+    if num_columns == 2 && comparison == Comparison::Pairwise {
+        print_puzzle(distance(0, 1), unpaired_len(0, 1), similarity(0, 1));
+    } else {
+        for (a, b) in comparison.column_pairs(num_columns) {
+            println!(
+                "Columns {} and {}: distance {}, similarity {}",
+                a,
+                b,
+                distance(a, b),
+                similarity(a, b)
+            );
+        }
+    }
+}
+
+/// How the distance between two sorted lists is measured, on the differences of their pairs.
+/// Distances are `u128`, so that even squares of the largest differences add up without
+/// overflow.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Metric {
+    /// Sum of the absolute differences, as in the puzzle.
+    Absolute,
+    /// Sum of the squared differences.
+    Squared,
+    /// Largest absolute difference.
+    Chebyshev,
+}
+
+impl Metric {
+    fn from_name(name: &str) -> Result<Metric, String> {
+        match name {
+            "absolute" => Ok(Metric::Absolute),
+            "squared" => Ok(Metric::Squared),
+            "chebyshev" => Ok(Metric::Chebyshev),
+            _ => Err(format!(
+                "unknown metric {:?}, expected absolute, squared or chebyshev",
+                name
+            )),
+        }
+    }
+
+    fn distance(&self, pairs: impl Iterator<Item = (u64, u64)>) -> u128 {
+        pairs.fold(0, |total, (a, b)| {
+            self.combine(total, self.pair_distance(a, b))
+        })
+    }
+
+    /// Contribution of a single pair.
+    fn pair_distance(&self, a: u64, b: u64) -> u128 {
+        let diff = a.abs_diff(b) as u128;
+        match self {
+            Metric::Absolute | Metric::Chebyshev => diff,
+            Metric::Squared => diff * diff,
+        }
+    }
+
+    /// Add a pair's contribution to the distance so far.
+    fn combine(&self, total: u128, pair: u128) -> u128 {
+        match self {
+            Metric::Absolute | Metric::Squared => total + pair,
+            Metric::Chebyshev => total.max(pair),
+        }
+    }
+}
+
+/// Which columns are compared with each other.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Comparison {
+    /// Every column with every other column.
+    Pairwise,
+    /// Every column with the given reference column.
+    ToReference(usize),
+}
+
+impl Comparison {
+    /// Whether the comparison is possible with `n` columns.
+    fn check(&self, n: usize) -> Result<(), String> {
+        match *self {
+            Comparison::ToReference(r) if r >= n => Err(format!(
+                "reference column {} out of range, the input has {} columns",
+                r, n
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The column pairs `(a, b)` to compare, out of `n` columns.
+    fn column_pairs(&self, n: usize) -> Vec<(usize, usize)> {
+        match *self {
+            Comparison::Pairwise => (0..n)
+                .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+                .collect(),
+            Comparison::ToReference(r) => (0..n).filter(|&c| c != r).map(|c| (r, c)).collect(),
+        }
+    }
+}
+
+/// The location lists of the historians, one per column and each kept sorted.
+///
+/// The lists may have different lengths. A distance then only pairs up the smallest entries
+/// until the shorter list runs out, the rest of the longer list is available via `unpaired`.
+struct LocationLists {
+    columns: Vec<Vec<u64>>,
+}
+
+impl LocationLists {
+    fn from_columns(columns: Vec<Vec<u64>>) -> LocationLists {
+        let mut columns = columns;
+        for col in columns.iter_mut() {
+            col.sort_unstable();
+        }
+        LocationLists { columns }
+    }
+
+    /// Read whitespace separated columns, one entry per column on each line. All lines need as
+    /// many columns as the first one, empty lines are skipped.
+    fn from_text(s: &str) -> Result<LocationLists, String> {
+        let mut columns: Vec<Vec<u64>> = vec![];
+        for (lt, line) in s.lines().enumerate() {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.is_empty() {
+                continue;
+            }
+            if columns.is_empty() {
+                columns = vec![vec![]; parts.len()];
+            }
+            if parts.len() != columns.len() {
+                return Err(format!(
+                    "line {}: expected {} columns",
+                    lt + 1,
+                    columns.len()
+                ));
+            }
+            for (col, part) in columns.iter_mut().zip(parts) {
+                col.push(
+                    part.parse()
+                        .map_err(|e| format!("line {}: {}", lt + 1, e))?,
+                );
+            }
+        }
+        Ok(LocationLists::from_columns(columns))
+    }
+
+    fn num_columns(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// The sorted entries of two columns, paired up smallest to smallest.
+    fn paired(&self, a: usize, b: usize) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.columns[a]
+            .iter()
+            .copied()
+            .zip(self.columns[b].iter().copied())
+    }
+
+    fn distance_between(&self, a: usize, b: usize, metric: Metric) -> u128 {
+        metric.distance(self.paired(a, b))
+    }
+
+    /// Sum of `weight(entry, occurrences)` for every entry of column `a`, with its number of
+    /// occurrences in column `b`.
+    fn similarity_between(&self, a: usize, b: usize, weight: impl Fn(u64, u64) -> u64) -> u64 {
+        let mut occurrences: HashMap<u64, u64> = HashMap::new();
+        for val in &self.columns[b] {
+            *occurrences.entry(*val).or_insert(0) += 1;
+        }
+        self.columns[a]
+            .iter()
+            .map(|val| weight(*val, *occurrences.get(val).unwrap_or(&0)))
+            .sum()
+    }
+
+    /// Entries of the longer of two lists that have no partner in the distance, sorted.
+    fn unpaired(&self, a: usize, b: usize) -> &[u64] {
+        let (left, right) = (&self.columns[a], &self.columns[b]);
+        if left.len() > right.len() {
+            &left[right.len()..]
+        } else {
+            &right[left.len()..]
+        }
+    }
+
+    /// Every pair of columns `a` and `b` with its contribution to the distance.
+    fn pair_diffs(&self, a: usize, b: usize, metric: Metric) -> Vec<PairDiff> {
+        let mut cumulative = 0;
+        self.paired(a, b)
+            .enumerate()
+            .map(|(index, (left, right))| {
+                let distance = metric.pair_distance(left, right);
+                cumulative = metric.combine(cumulative, distance);
+                PairDiff {
+                    index,
+                    left,
+                    right,
+                    distance,
+                    cumulative,
+                }
+            })
+            .collect()
+    }
+
+    /// Distinct values of column `a` that never appear in column `b`, sorted.
+    fn only_in(&self, a: usize, b: usize) -> Vec<u64> {
+        let mut only: Vec<u64> = self.columns[a]
+            .iter()
+            .copied()
+            .filter(|val| self.columns[b].binary_search(val).is_err())
+            .collect();
+        only.dedup();
+        only
+    }
+}
+
+/// One sorted pair in the diff report.
+#[derive(Debug, Clone, PartialEq)]
+struct PairDiff {
+    index: usize,
+    left: u64,
+    right: u64,
+    distance: u128,
+    /// Distance of all pairs up to and including this one.
+    cumulative: u128,
+}
+
+impl PairDiff {
+    fn table_row(&self) -> String {
+        format!(
+            "{:>6}  {:>8}  {:>8}  {:>8}  {:>10}",
+            self.index, self.left, self.right, self.distance, self.cumulative
+        )
+    }
+}
+
+/// The `k` pairs contributing most to the distance, largest first and by index on ties.
+fn top_contributors(diffs: &[PairDiff], k: usize) -> Vec<&PairDiff> {
+    let mut top: Vec<&PairDiff> = diffs.iter().collect();
+    top.sort_by_key(|d| std::cmp::Reverse(d.distance));
+    top.truncate(k);
+    top
+}
+
+/// Print where columns `a` and `b` diverge: every pair, the largest contributors and the values
+/// found in only one of them.
+fn print_diff(lists: &LocationLists, a: usize, b: usize, metric: Metric, top: usize) {
+    let diffs = lists.pair_diffs(a, b, metric);
+    println!(
+        "{:>6}  {:>8}  {:>8}  {:>8}  {:>10}",
+        "pair", "left", "right", "distance", "cumulative"
+    );
+    for diff in &diffs {
+        println!("{}", diff.table_row());
+    }
+    if !lists.unpaired(a, b).is_empty() {
+        println!("Without partner: {:?}", lists.unpaired(a, b));
+    }
+
+    println!("Top {} contributors:", top);
+    for diff in top_contributors(&diffs, top) {
+        println!("{}", diff.table_row());
+    }
+    println!("Only in column {}: {:?}", a, lists.only_in(a, b));
+    println!("Only in column {}: {:?}", b, lists.only_in(b, a));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for line in lines {
-        let line = line.unwrap();
-        let mut parts = line.split_whitespace();
-        let a: u64 = parts.next().unwrap().parse().unwrap();
-        let b: u64 = parts.next().unwrap().parse().unwrap();
-        col1.push(a);
-        col2.push(b);
+    const DATA: &str = "3   4
+4   3
+2   5
+1   3
+3   9
+3   3";
+
+    #[test]
+    fn test_example() {
+        let lists = LocationLists::from_text(DATA).unwrap();
+        assert_eq!(lists.distance_between(0, 1, Metric::Absolute), 11);
+        assert_eq!(lists.similarity_between(0, 1, |val, occ| val * occ), 31);
+        assert!(lists.unpaired(0, 1).is_empty());
+    }
+
+    #[test]
+    fn test_unequal_lengths() {
+        let lists = LocationLists::from_columns(vec![vec![3, 1, 2], vec![10, 2]]);
+        assert_eq!(lists.distance_between(0, 1, Metric::Absolute), 1 + 8);
+        assert_eq!(lists.similarity_between(0, 1, |val, occ| val * occ), 2);
+        assert_eq!(lists.unpaired(0, 1), &[3]);
+    }
+
+    #[test]
+    fn test_bad_text() {
+        assert!(LocationLists::from_text("1 2\n3\n").is_err());
+        assert!(LocationLists::from_text("1 x\n").is_err());
     }
 
-    // Now sor the columns
-    col1.sort();
-    col2.sort();
+    #[test]
+    fn test_metrics() {
+        let lists = LocationLists::from_text(DATA).unwrap();
+        // sorted pairs: (1, 3), (2, 3), (3, 3), (3, 4), (3, 5), (4, 9), squares 4 + 1 + 0 + 1 + 4 + 25
+        assert_eq!(lists.distance_between(0, 1, Metric::Squared), 35);
+        assert_eq!(lists.distance_between(0, 1, Metric::Chebyshev), 5);
+        assert_eq!(lists.distance_between(1, 0, Metric::Absolute), 11);
+        assert!(Metric::from_name("manhattan").is_err());
+    }
 
-    // Subract the two columns
-    let mut result = 0;
-    for it in 0..col1.len() {
-        result += col1[it].abs_diff(col2[it]);
+    #[test]
+    fn test_squared_no_overflow() {
+        let lists = LocationLists::from_columns(vec![vec![0, 0], vec![1, u64::MAX]]);
+        let square = (u64::MAX as u128) * (u64::MAX as u128);
+        assert_eq!(lists.distance_between(0, 1, Metric::Squared), square + 1);
     }
 
-    println!("Distance: {}", result);
+    #[test]
+    fn test_three_columns() {
+        let lists = LocationLists::from_text("1 2 1\n5 4 6\n3 3 3\n").unwrap();
+        assert_eq!(
+            Comparison::Pairwise.column_pairs(3),
+            vec![(0, 1), (0, 2), (1, 2)]
+        );
+        assert_eq!(
+            Comparison::ToReference(2).column_pairs(3),
+            vec![(2, 0), (2, 1)]
+        );
+        assert_eq!(lists.distance_between(0, 2, Metric::Absolute), 1);
+        assert_eq!(lists.distance_between(1, 2, Metric::Chebyshev), 2);
+        // count matches only, ignoring the values
+        assert_eq!(lists.similarity_between(0, 2, |_, occ| occ), 2);
+    }
 
-    // Part 2 - calculate the similarity score
-    let mut result = 0;
-    for c1 in col1 {
-        let occ_c2 = col2.iter().filter(|&x| *x == c1).count();
-        result += c1 * occ_c2 as u64;
+    #[test]
+    fn test_reference_column() {
+        assert!(Comparison::ToReference(2).check(3).is_ok());
+        assert!(Comparison::ToReference(3).check(3).is_err());
+        assert!(Comparison::Pairwise.check(0).is_ok());
     }
 
-    println!("Similarity score: {}", result);
+    #[test]
+    fn test_diff() {
+        let lists = LocationLists::from_text(DATA).unwrap();
+        let diffs = lists.pair_diffs(0, 1, Metric::Absolute);
+        let cumulative: Vec<u128> = diffs.iter().map(|d| d.cumulative).collect();
+        assert_eq!(cumulative, vec![2, 3, 3, 4, 6, 11]);
+        let top: Vec<usize> = top_contributors(&diffs, 3)
+            .iter()
+            .map(|d| d.index)
+            .collect();
+        assert_eq!(top, vec![5, 0, 4]);
+
+        let diffs = lists.pair_diffs(0, 1, Metric::Chebyshev);
+        assert_eq!(diffs.last().unwrap().cumulative, 5);
+
+        assert_eq!(lists.only_in(0, 1), vec![1, 2]);
+        assert_eq!(lists.only_in(1, 0), vec![5, 9]);
+    }
 }
@@ -0,0 +1,222 @@
+//! Batch evaluation of many reports at once with ndarray, along the lines of `alternative.py`.
+//!
+//! Reports are grouped by length into one 2D array per length, one report per row. Differences
+//! and range checks are then computed for whole arrays. A single removal is checked for all rows
+//! at once from running counts of bad steps and plateaus, like `dampener_index_directed`. More
+//! removals fall back to trying every set of columns to drop, which grows quickly with the
+//! number of removals.
+use std::collections::BTreeMap;
+
+use ndarray::{s, Array1, Array2, ArrayView2, Axis};
+
+use crate::SafetyPolicy;
+
+/// Same counts as `check_input`: reports safe as they are, and safe with the dampener.
+pub fn check_input_batch(input: &[Vec<isize>], policy: &SafetyPolicy) -> (usize, usize) {
+    let mut by_len: BTreeMap<usize, Vec<&Vec<isize>>> = BTreeMap::new();
+    for report in input {
+        by_len.entry(report.len()).or_default().push(report);
+    }
+
+    let mut total_safe = 0;
+    let mut total_safe_damp = 0;
+    for (len, reports) in by_len {
+        // without any step, reports of no or one level are always safe
+        if len < 2 {
+            total_safe += reports.len();
+            total_safe_damp += reports.len();
+            continue;
+        }
+        let flat: Vec<isize> = reports.iter().flat_map(|r| r.iter().copied()).collect();
+        let levels = Array2::from_shape_vec((reports.len(), len), flat).unwrap();
+
+        let safe = rows_safe(levels.view(), policy);
+        total_safe += safe.iter().filter(|&&s| s).count();
+
+        let mut safe_damp = safe;
+        if policy.max_removals >= 1 {
+            safe_damp = safe_damp | rows_safe_one_removal(levels.view(), policy);
+        }
+        // a single level left is always safe, so at most len - 1 removals are worth trying
+        for removed in 2..=policy.max_removals.min(len - 1) {
+            for columns in combinations(len, removed) {
+                let kept: Vec<usize> = (0..len).filter(|c| !columns.contains(c)).collect();
+                let rest = levels.select(Axis(1), &kept);
+                safe_damp = safe_damp | rows_safe(rest.view(), policy);
+            }
+        }
+        total_safe_damp += safe_damp.iter().filter(|&&s| s).count();
+    }
+    (total_safe, total_safe_damp)
+}
+
+/// For every row, whether its steps follow the policy without removing a level.
+fn rows_safe(levels: ArrayView2<isize>, policy: &SafetyPolicy) -> Array1<bool> {
+    let diff = &levels.slice(s![.., 1..]) - &levels.slice(s![.., ..-1]);
+    let mut safe = Array1::from_elem(levels.nrows(), false);
+    for &sign in policy.direction.signs() {
+        let (bad, plateaus) = step_counts(diff.view(), policy, sign);
+        let no_bad = bad.sum_axis(Axis(1)).mapv(|b| b == 0);
+        let few_plateaus = plateaus.sum_axis(Axis(1)).mapv(|p| p <= policy.plateaus);
+        safe = safe | (no_bad & few_plateaus);
+    }
+    safe
+}
+
+/// For every row, whether removing one of its levels makes its steps follow the policy.
+///
+/// Removing level `i` keeps the steps before level `i - 1` and after level `i + 1`, and adds
+/// the step bridging `i - 1` and `i + 1`. The kept steps are counted from running sums.
+fn rows_safe_one_removal(levels: ArrayView2<isize>, policy: &SafetyPolicy) -> Array1<bool> {
+    let (rows, len) = levels.dim();
+    let diff = &levels.slice(s![.., 1..]) - &levels.slice(s![.., ..-1]);
+    // bridge[.., i]: the step from level i to level i + 2
+    let bridge = &levels.slice(s![.., 2..]) - &levels.slice(s![.., ..-2]);
+    // running[.., j]: sum of the first j steps
+    let running = |steps: Array2<usize>| {
+        let mut sums = Array2::zeros((rows, len));
+        sums.slice_mut(s![.., 1..]).assign(&steps);
+        sums.accumulate_axis_inplace(Axis(1), |&prev, cur| *cur += prev);
+        sums
+    };
+
+    let mut safe = Array1::from_elem(rows, false);
+    for &sign in policy.direction.signs() {
+        let (bad, plateaus) = step_counts(diff.view(), policy, sign);
+        let (bridge_bad, bridge_plateaus) = step_counts(bridge.view(), policy, sign);
+        let (bad, plateaus) = (running(bad), running(plateaus));
+        let kept = |sums: &Array2<usize>, i: usize| {
+            let before = sums.column(i.saturating_sub(1));
+            let after = &sums.column(len - 1) - &sums.column((i + 1).min(len - 1));
+            &before + &after
+        };
+        for i in 0..len {
+            let mut bad_kept = kept(&bad, i);
+            let mut plateaus_kept = kept(&plateaus, i);
+            if i > 0 && i < len - 1 {
+                bad_kept += &bridge_bad.column(i - 1);
+                plateaus_kept += &bridge_plateaus.column(i - 1);
+            }
+            safe =
+                safe | (bad_kept.mapv(|b| b == 0) & plateaus_kept.mapv(|p| p <= policy.plateaus));
+        }
+    }
+    safe
+}
+
+/// Bad steps and tolerated plateaus among `diff` in direction `sign`, as 0 or 1 per step.
+fn step_counts(
+    diff: ArrayView2<isize>,
+    policy: &SafetyPolicy,
+    sign: isize,
+) -> (Array2<usize>, Array2<usize>) {
+    let good = diff.mapv(|d| policy.steps.contains(&(d * sign)));
+    let flat = diff.mapv(|d| d == 0);
+    let bad = (&good | &flat).mapv(|ok| !ok as usize);
+    let plateaus = (&flat & &good.mapv(|g| !g)).mapv(|p| p as usize);
+    (bad, plateaus)
+}
+
+/// All sets of `k` column indices out of `n`, in increasing order.
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![vec![]];
+    }
+    (k - 1..n)
+        .flat_map(|last| {
+            combinations(last, k - 1).into_iter().map(move |mut c| {
+                c.push(last);
+                c
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_input, Direction};
+
+    #[test]
+    fn check_combinations() {
+        assert_eq!(combinations(3, 2), vec![vec![0, 1], vec![0, 2], vec![1, 2]]);
+        assert_eq!(combinations(4, 1).len(), 4);
+        assert_eq!(combinations(5, 3).len(), 10);
+    }
+
+    #[test]
+    fn check_one_removal() {
+        let levels = ndarray::array![
+            [7, 6, 4, 2, 1],
+            [1, 2, 7, 8, 9],
+            [1, 3, 2, 4, 5],
+            [8, 6, 4, 4, 1]
+        ];
+        let safe = rows_safe_one_removal(levels.view(), &SafetyPolicy::default());
+        assert_eq!(safe.to_vec(), vec![true, false, true, true]);
+        // the first or the last level
+        let levels = ndarray::array![[9, 1, 2, 3], [1, 2, 3, 9]];
+        let safe = rows_safe_one_removal(levels.view(), &SafetyPolicy::default());
+        assert_eq!(safe.to_vec(), vec![true, true]);
+        let levels = ndarray::array![[1, 9]];
+        let safe = rows_safe_one_removal(levels.view(), &SafetyPolicy::default());
+        assert_eq!(safe.to_vec(), vec![true]);
+    }
+
+    #[test]
+    fn check_short_reports() {
+        let input: Vec<Vec<isize>> = "7 6 4 2 1\n\n1 3 2 4 5\n9\n"
+            .lines()
+            .map(|line| {
+                line.split_whitespace()
+                    .map(|x| x.parse().unwrap())
+                    .collect()
+            })
+            .collect();
+        let policy = SafetyPolicy::default();
+        assert_eq!(check_input(&input, &policy), (3, 4));
+        assert_eq!(check_input_batch(&input, &policy), (3, 4));
+        assert_eq!(check_input_batch(&input[..3], &policy), (2, 3));
+    }
+
+    #[test]
+    fn check_batch_matches_check_input() {
+        let input: Vec<Vec<isize>> = std::fs::read_to_string("input")
+            .unwrap()
+            .lines()
+            .map(|line| {
+                line.split_whitespace()
+                    .map(|x| x.parse().unwrap())
+                    .collect()
+            })
+            .collect();
+        let policies = [
+            SafetyPolicy::default(),
+            SafetyPolicy {
+                max_removals: 2,
+                plateaus: 1,
+                ..SafetyPolicy::default()
+            },
+            SafetyPolicy {
+                steps: 2..=4,
+                max_removals: 0,
+                direction: Direction::Decreasing,
+                plateaus: 0,
+            },
+            SafetyPolicy {
+                steps: 0..=3,
+                max_removals: 3,
+                direction: Direction::Increasing,
+                plateaus: 2,
+            },
+        ];
+        for policy in policies {
+            assert_eq!(
+                check_input_batch(&input, &policy),
+                check_input(&input, &policy),
+                "{:?}",
+                policy
+            );
+        }
+    }
+}
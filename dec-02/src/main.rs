@@ -1,11 +1,21 @@
 use std::fs::File;
 use std::io::{self, BufRead};
+use std::ops::RangeInclusive;
 use std::path::Path;
 
+mod batch;
+
+use batch::check_input_batch;
+
 fn main() {
     let filename = "input";
     let lines = read_lines(filename).unwrap();
 
+    // Optional policy: `--steps MIN-MAX`, `--max-removals K`,
+    // `--direction increasing|decreasing|either` and `--plateaus N`
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let policy = SafetyPolicy::from_args(&args).unwrap();
+
     // process the reports
     let mut overall_vec = vec![];
     for line in lines.map_while(Result::ok) {
@@ -16,58 +26,603 @@ fn main() {
         overall_vec.push(entries);
     }
 
-    let (total_safe, total_safe_dampened) = check_input(overall_vec);
+    // `--batch` evaluates the reports in bulk with ndarray, for very large report files
+    let (total_safe, total_safe_dampened) = if args.iter().any(|a| a == "--batch") {
+        check_input_batch(&overall_vec, &policy)
+    } else {
+        check_input(&overall_vec, &policy)
+    };
     println!("Total safe reports: {}", total_safe);
     println!("Total safe reports dampened: {}", total_safe_dampened);
+
+    // Optional `--explain` / `--explain-json`: list why each report counts or not
+    let explanations = || {
+        overall_vec
+            .iter()
+            .enumerate()
+            .map(|(it, report)| explain_report(it, report, &policy))
+    };
+    if args.iter().any(|a| a == "--explain") {
+        println!("{:>6}  {:<5}  {:<8}  reason", "report", "safe", "dampened");
+        for expl in explanations() {
+            println!("{}", expl.table_row());
+        }
+    } else if args.iter().any(|a| a == "--explain-json") {
+        let rows: Vec<String> = explanations().map(|e| e.to_json()).collect();
+        println!("[\n  {}\n]", rows.join(",\n  "));
+    }
+
+    // Optional `--diagnostics` / `--diagnostics-csv`: status, first bad step and removed levels,
+    // with `--unsafe-only` to audit the reports that fail
+    let unsafe_only = args.iter().any(|a| a == "--unsafe-only");
+    let audited = || {
+        diagnose(&overall_vec, &policy)
+            .into_iter()
+            .filter(|d| !unsafe_only || d.status == Status::Unsafe)
+    };
+    if args.iter().any(|a| a == "--diagnostics") {
+        println!("{}", ReportDiagnostics::TABLE_HEADER);
+        for diag in audited() {
+            println!("{}", diag.table_row());
+        }
+    } else if args.iter().any(|a| a == "--diagnostics-csv") {
+        println!("{}", ReportDiagnostics::CSV_HEADER);
+        for diag in audited() {
+            println!("{}", diag.csv_row());
+        }
+    }
 }
 
-fn check_input(input: Vec<Vec<isize>>) -> (usize, usize) {
-    let mut total_safe = 0;
-    let mut total_safe_damp = 0;
+/// Direction the levels of a report have to go.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Direction {
+    Increasing,
+    Decreasing,
+    Either,
+}
 
-    for inp in input {
-        if process_report(&inp, false) {
-            total_safe += 1;
+impl Direction {
+    fn from_name(name: &str) -> Result<Direction, String> {
+        match name {
+            "increasing" => Ok(Direction::Increasing),
+            "decreasing" => Ok(Direction::Decreasing),
+            "either" => Ok(Direction::Either),
+            _ => Err(format!("unknown direction: {}", name)),
         }
+    }
 
-        if process_report(&inp, true) {
-            total_safe_damp += 1;
+    /// Signs of the steps that are allowed, `1` for increasing and `-1` for decreasing.
+    fn signs(&self) -> &'static [isize] {
+        match self {
+            Direction::Increasing => &[1],
+            Direction::Decreasing => &[-1],
+            Direction::Either => &[1, -1],
         }
     }
-    (total_safe, total_safe_damp)
+
+    fn from_sign(sign: isize) -> Direction {
+        if sign > 0 {
+            Direction::Increasing
+        } else {
+            Direction::Decreasing
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Direction::Increasing => "increasing",
+            Direction::Decreasing => "decreasing",
+            Direction::Either => "either",
+        }
+    }
+}
+
+/// What makes a report safe.
+#[derive(Debug, Clone, PartialEq)]
+struct SafetyPolicy {
+    /// Allowed size of a step in the report's direction.
+    steps: RangeInclusive<isize>,
+    /// Number of levels the dampener may remove.
+    max_removals: usize,
+    direction: Direction,
+    /// Number of steps between equal levels that are tolerated although outside `steps`.
+    plateaus: usize,
+}
+
+/// How a step between two levels counts under a policy.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Step {
+    Good,
+    Plateau,
+    Bad,
+}
+
+/// A way to make a report safe: its direction and the levels to remove.
+#[derive(Debug, Clone, PartialEq)]
+struct Outcome {
+    direction: Direction,
+    removed: Vec<usize>,
+}
+
+impl Default for SafetyPolicy {
+    /// The puzzle's policy: steps of 1 to 3 in either direction, and one level can be removed.
+    fn default() -> SafetyPolicy {
+        SafetyPolicy {
+            steps: 1..=3,
+            max_removals: 1,
+            direction: Direction::Either,
+            plateaus: 0,
+        }
+    }
+}
+
+impl SafetyPolicy {
+    fn from_args(args: &[String]) -> Result<SafetyPolicy, String> {
+        let mut policy = SafetyPolicy::default();
+        let mut it = args.iter();
+        while let Some(arg) = it.next() {
+            let mut value = || it.next().ok_or(format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--steps" => {
+                    let bad = || "--steps needs MIN-MAX".to_string();
+                    let (min, max) = value()?.split_once('-').ok_or_else(bad)?;
+                    policy.steps =
+                        min.parse().map_err(|_| bad())?..=max.parse().map_err(|_| bad())?;
+                }
+                "--max-removals" => {
+                    policy.max_removals = value()?
+                        .parse()
+                        .map_err(|_| "--max-removals needs a number")?
+                }
+                "--direction" => policy.direction = Direction::from_name(value()?)?,
+                "--plateaus" => {
+                    policy.plateaus = value()?.parse().map_err(|_| "--plateaus needs a number")?
+                }
+                _ => (),
+            }
+        }
+        Ok(policy)
+    }
+
+    /// The same policy without the dampener.
+    fn undampened(&self) -> SafetyPolicy {
+        SafetyPolicy {
+            max_removals: 0,
+            ..self.clone()
+        }
+    }
+
+    fn step(&self, from: isize, to: isize, sign: isize) -> Step {
+        if self.steps.contains(&((to - from) * sign)) {
+            Step::Good
+        } else if to == from {
+            Step::Plateau
+        } else {
+            Step::Bad
+        }
+    }
+
+    /// How to make the report safe with the fewest removals, if it can be done.
+    fn outcome(&self, entries: &[isize]) -> Option<Outcome> {
+        self.direction
+            .signs()
+            .iter()
+            .filter_map(|&sign| {
+                let removed = self.removals_directed(entries, sign)?;
+                Some(Outcome {
+                    direction: Direction::from_sign(sign),
+                    removed,
+                })
+            })
+            .min_by(|a, b| (a.removed.len(), &a.removed).cmp(&(b.removed.len(), &b.removed)))
+    }
+
+    fn is_safe(&self, entries: &[isize]) -> bool {
+        self.outcome(entries).is_some()
+    }
+
+    /// Levels to remove so that all steps go in direction `sign`.
+    fn removals_directed(&self, entries: &[isize], sign: isize) -> Option<Vec<usize>> {
+        if self.all_good(entries, sign) {
+            return Some(vec![]);
+        }
+        match self.max_removals {
+            0 => None,
+            1 => self
+                .dampener_index_directed(entries, sign)
+                .map(|ind| vec![ind]),
+            _ => self.removals_dp(entries, sign),
+        }
+    }
+
+    /// Plateaus used by a step in direction `sign`, or None if it is bad.
+    fn plateaus_used(&self, from: isize, to: isize, sign: isize) -> Option<usize> {
+        match self.step(from, to, sign) {
+            Step::Good => Some(0),
+            Step::Plateau => Some(1),
+            Step::Bad => None,
+        }
+    }
+
+    /// Whether the report is safe in direction `sign` without removing a level.
+    fn all_good(&self, entries: &[isize], sign: isize) -> bool {
+        entries
+            .windows(2)
+            .map(|pair| self.plateaus_used(pair[0], pair[1], sign))
+            .sum::<Option<usize>>()
+            .is_some_and(|p| p <= self.plateaus)
+    }
+
+    /// Index of the first level whose removal leaves only good steps in direction `sign`.
+    ///
+    /// Linear time: a level can go if all steps before and after it are good, its two
+    /// neighbours are a good step apart, and there are not too many plateaus in total.
+    fn dampener_index_directed(&self, entries: &[isize], sign: isize) -> Option<usize> {
+        let n = entries.len();
+        let plateaus = |a: isize, b: isize| self.plateaus_used(a, b, sign);
+
+        // suffix[i]: plateaus among the steps from level i to the end, or None if one is bad
+        let mut suffix = vec![Some(0); n + 1];
+        for i in (0..n.saturating_sub(1)).rev() {
+            suffix[i] = suffix[i + 1]
+                .zip(plateaus(entries[i], entries[i + 1]))
+                .map(|(a, b)| a + b);
+        }
+
+        // plateaus among the steps before level i
+        let mut prefix = Some(0);
+        for i in 0..n {
+            let bridge = if i == 0 || i == n - 1 {
+                Some(0)
+            } else {
+                plateaus(entries[i - 1], entries[i + 1])
+            };
+            let total = prefix
+                .zip(bridge)
+                .zip(suffix[i + 1])
+                .map(|((a, b), c)| a + b + c);
+            if total.is_some_and(|p| p <= self.plateaus) {
+                return Some(i);
+            }
+            if i > 0 {
+                prefix = prefix
+                    .zip(plateaus(entries[i - 1], entries[i]))
+                    .map(|(a, b)| a + b);
+            }
+        }
+        None
+    }
+
+    /// Fewest levels to remove so that all steps go in direction `sign`, for any number of
+    /// removals. Dynamic programming over the last level kept and the plateaus used so far.
+    fn removals_dp(&self, entries: &[isize], sign: isize) -> Option<Vec<usize>> {
+        let n = entries.len();
+        let k = self.max_removals;
+        // best[i][p]: fewest removals among levels 0..=i keeping level i with p plateaus,
+        // and the (level, plateaus) kept before it
+        type Best = Option<(usize, Option<(usize, usize)>)>;
+        let mut best: Vec<Vec<Best>> = vec![vec![None; self.plateaus + 1]; n];
+
+        for i in 0..n {
+            if i <= k {
+                best[i][0] = Some((i, None));
+            }
+            for j in i.saturating_sub(k + 1)..i {
+                let Some(used) = self.plateaus_used(entries[j], entries[i], sign) else {
+                    continue;
+                };
+                for p in 0..=self.plateaus {
+                    let Some((removed, _)) = best[j][p] else {
+                        continue;
+                    };
+                    let removed = removed + i - j - 1;
+                    let p2 = p + used;
+                    if p2 <= self.plateaus
+                        && removed <= k
+                        && best[i][p2].is_none_or(|(r, _)| removed < r)
+                    {
+                        best[i][p2] = Some((removed, Some((j, p))));
+                    }
+                }
+            }
+        }
+
+        // best last level to keep, removing everything after it
+        let (_, last, p) = (0..n)
+            .flat_map(|i| (0..=self.plateaus).map(move |p| (i, p)))
+            .filter_map(|(i, p)| best[i][p].map(|(r, _)| (r + n - 1 - i, i, p)))
+            .filter(|(r, _, _)| *r <= k)
+            .min()?;
+
+        let mut kept = vec![false; n];
+        let mut cur = Some((last, p));
+        while let Some((i, p)) = cur {
+            kept[i] = true;
+            cur = best[i][p].unwrap().1;
+        }
+        Some((0..n).filter(|&i| !kept[i]).collect())
+    }
+}
+
+/// Why a report was judged safe or unsafe, with and without dampening.
+#[derive(Debug, PartialEq)]
+struct ReportExplanation {
+    index: usize,
+    levels: Vec<isize>,
+    safe: bool,
+    safe_dampened: bool,
+    reason: String,
+}
+
+impl ReportExplanation {
+    fn table_row(&self) -> String {
+        format!(
+            "{:>6}  {:<5}  {:<8}  {}",
+            self.index, self.safe, self.safe_dampened, self.reason
+        )
+    }
+
+    fn to_json(&self) -> String {
+        json_object(&[
+            ("index", self.index.to_string()),
+            ("levels", json_array(&self.levels)),
+            ("safe", self.safe.to_string()),
+            ("safe_dampened", self.safe_dampened.to_string()),
+            ("reason", json_string(&self.reason)),
+        ])
+    }
+}
+
+/// Explain the verdict of the policy for one report.
+fn explain_report(index: usize, entries: &[isize], policy: &SafetyPolicy) -> ReportExplanation {
+    let diag = diagnose_report(index, entries, policy);
+    let steps = format!("by {} to {}", policy.steps.start(), policy.steps.end());
+
+    let reason = match diag.first_bad {
+        None => format!("all steps {} {}", diag.direction.name(), steps),
+        Some((ind, step)) => {
+            let bad = format!(
+                "step {} from {} to {} at index {}",
+                step,
+                entries[ind],
+                entries[ind + 1],
+                ind
+            );
+            match diag.status {
+                Status::SafeAfterDampening if diag.removed.len() == 1 => format!(
+                    "{}, safe after removing level {} at index {}",
+                    bad, diag.removed[0].1, diag.removed[0].0
+                ),
+                Status::SafeAfterDampening => {
+                    let levels: Vec<String> =
+                        diag.removed.iter().map(|r| r.1.to_string()).collect();
+                    let indices: Vec<String> =
+                        diag.removed.iter().map(|r| r.0.to_string()).collect();
+                    format!(
+                        "{}, safe after removing levels {} at indices {}",
+                        bad,
+                        levels.join(", "),
+                        indices.join(", ")
+                    )
+                }
+                _ if policy.max_removals == 1 => format!("{}, no single removal helps", bad),
+                _ => format!(
+                    "{}, no removal of up to {} levels helps",
+                    bad, policy.max_removals
+                ),
+            }
+        }
+    };
+
+    ReportExplanation {
+        index,
+        levels: entries.to_vec(),
+        safe: diag.status == Status::Safe,
+        safe_dampened: diag.status != Status::Unsafe,
+        reason,
+    }
+}
+
+/// Verdict of the policy for a report.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Status {
+    Safe,
+    SafeAfterDampening,
+    Unsafe,
+}
+
+impl Status {
+    fn name(&self) -> &'static str {
+        match self {
+            Status::Safe => "safe",
+            Status::SafeAfterDampening => "dampened",
+            Status::Unsafe => "unsafe",
+        }
+    }
+}
+
+/// Where a report breaks the policy, and what the dampener did about it.
+#[derive(Debug, Clone, PartialEq)]
+struct ReportDiagnostics {
+    index: usize,
+    status: Status,
+    /// Direction the report is safe in, or else the one it was checked in.
+    direction: Direction,
+    /// Index and size of the first step that breaks the policy without dampening.
+    first_bad: Option<(usize, isize)>,
+    /// Index and level of every level the dampener removed.
+    removed: Vec<(usize, isize)>,
+}
+
+impl ReportDiagnostics {
+    const TABLE_HEADER: &'static str = "report  status    direction   bad  delta  removed";
+    const CSV_HEADER: &'static str =
+        "report,status,direction,bad_index,bad_delta,removed_indices,removed_levels";
+
+    fn table_row(&self) -> String {
+        let (bad, delta) = match self.first_bad {
+            Some((ind, delta)) => (ind.to_string(), delta.to_string()),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        let removed: Vec<String> = self
+            .removed
+            .iter()
+            .map(|(ind, level)| format!("{} at {}", level, ind))
+            .collect();
+        format!(
+            "{:>6}  {:<8}  {:<10}  {:>4}  {:>5}  {}",
+            self.index,
+            self.status.name(),
+            self.direction.name(),
+            bad,
+            delta,
+            if removed.is_empty() {
+                "-".to_string()
+            } else {
+                removed.join(", ")
+            }
+        )
+    }
+
+    fn csv_row(&self) -> String {
+        let (bad, delta) = match self.first_bad {
+            Some((ind, delta)) => (ind.to_string(), delta.to_string()),
+            None => (String::new(), String::new()),
+        };
+        let indices: Vec<String> = self.removed.iter().map(|r| r.0.to_string()).collect();
+        let levels: Vec<String> = self.removed.iter().map(|r| r.1.to_string()).collect();
+        format!(
+            "{},{},{},{},{},{},{}",
+            self.index,
+            self.status.name(),
+            self.direction.name(),
+            bad,
+            delta,
+            indices.join(";"),
+            levels.join(";")
+        )
+    }
 }
 
-/// Process each individual report and return true / false if it is safe / unsafe
-fn process_report(entries: &[isize], dampened: bool) -> bool {
-    let mut parts = entries.to_vec();
-    let mut diff = parts[1..]
+/// Diagnose every report of the input.
+fn diagnose(input: &[Vec<isize>], policy: &SafetyPolicy) -> Vec<ReportDiagnostics> {
+    input
         .iter()
-        .zip(&parts[..parts.len() - 1])
-        .map(|(a, b)| a - b)
-        .collect::<Vec<isize>>();
+        .enumerate()
+        .map(|(it, report)| diagnose_report(it, report, policy))
+        .collect()
+}
+
+fn diagnose_report(index: usize, entries: &[isize], policy: &SafetyPolicy) -> ReportDiagnostics {
+    let outcome = policy.outcome(entries);
+    let (status, direction, removed) = match outcome {
+        Some(o) if o.removed.is_empty() => (Status::Safe, o.direction, vec![]),
+        Some(o) => {
+            let removed = o.removed.iter().map(|&ind| (ind, entries[ind])).collect();
+            (Status::SafeAfterDampening, o.direction, removed)
+        }
+        None => (
+            Status::Unsafe,
+            Direction::from_sign(checked_sign(entries, policy)),
+            vec![],
+        ),
+    };
+    let first_bad = match status {
+        Status::Safe => None,
+        _ => first_bad_step(entries, policy, direction.signs()[0]),
+    };
 
-    if !majority_positive(&diff) {
-        parts = parts.iter().map(|x| x * -1).collect();
-        diff = diff.iter().map(|x| x * -1).collect();
+    ReportDiagnostics {
+        index,
+        status,
+        direction,
+        first_bad,
+        removed,
     }
+}
 
-    if diff.iter().filter(|&x| !(1..=3).contains(x)).count() == 0 {
-        return true;
+/// Differences between neighbouring levels.
+fn diffs(entries: &[isize]) -> Vec<isize> {
+    entries[1..]
+        .iter()
+        .zip(&entries[..entries.len() - 1])
+        .map(|(a, b)| a - b)
+        .collect()
+}
+
+/// Direction to check a report in without dampening: the policy's, or else the report's
+/// majority direction.
+fn checked_sign(entries: &[isize], policy: &SafetyPolicy) -> isize {
+    match policy.direction {
+        Direction::Either if majority_positive(&diffs(entries)) => 1,
+        Direction::Either => -1,
+        dir => dir.signs()[0],
     }
+}
 
-    if !dampened {
-        return false;
+/// Index and size of the first step that breaks the policy without dampening, in direction
+/// `sign`.
+fn first_bad_step(entries: &[isize], policy: &SafetyPolicy, sign: isize) -> Option<(usize, isize)> {
+    let mut plateaus = 0;
+    entries
+        .windows(2)
+        .position(|pair| match policy.step(pair[0], pair[1], sign) {
+            Step::Good => false,
+            Step::Plateau => {
+                plateaus += 1;
+                plateaus > policy.plateaus
+            }
+            Step::Bad => true,
+        })
+        .map(|ind| (ind, entries[ind + 1] - entries[ind]))
+}
+
+/// A JSON object from field names and values that are already JSON.
+fn json_object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| format!("{}: {}", json_string(name), value))
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// A JSON array of numbers.
+fn json_array(items: impl IntoIterator<Item = impl std::fmt::Display>) -> String {
+    let items: Vec<String> = items.into_iter().map(|item| item.to_string()).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// Escape a string for JSON output.
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
     }
+    out.push('"');
+    out
+}
 
-    // Brute force dampening - whatever
-    for rit in 0..parts.len() {
-        let mut parts2 = parts.clone();
-        parts2.remove(rit);
-        if process_report(&parts2, false) {
-            return true;
+/// Count the reports that are safe as they are, and those that are safe with the dampener.
+fn check_input(input: &[Vec<isize>], policy: &SafetyPolicy) -> (usize, usize) {
+    let mut total_safe = 0;
+    let mut total_safe_damp = 0;
+    let undampened = policy.undampened();
+
+    for inp in input {
+        if undampened.is_safe(inp) {
+            total_safe += 1;
+        }
+
+        if policy.is_safe(inp) {
+            total_safe_damp += 1;
         }
     }
-    false
+    (total_safe, total_safe_damp)
 }
 
 /// Find out if majority of entries in a slice are positive
@@ -88,6 +643,18 @@ where
 mod tests {
     use super::*;
 
+    fn read_input() -> Vec<Vec<isize>> {
+        read_lines("input")
+            .unwrap()
+            .map_while(Result::ok)
+            .map(|line| {
+                line.split_whitespace()
+                    .map(|x| x.parse().unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
     #[test]
     fn check_example() {
         let input = vec![
@@ -98,7 +665,7 @@ mod tests {
             vec![8, 6, 4, 4, 1],
             vec![1, 3, 6, 7, 9],
         ];
-        let (total_safe, total_safe_dampened) = check_input(input);
+        let (total_safe, total_safe_dampened) = check_input(&input, &SafetyPolicy::default());
         assert_eq!(total_safe, 2);
         assert_eq!(total_safe_dampened, 4);
     }
@@ -121,7 +688,225 @@ mod tests {
             vec![1, 2, 3, 4, 3],
             vec![9, 8, 7, 6, 7],
         ];
-        let (_, total_safe_dampened) = check_input(input.clone());
+        let (_, total_safe_dampened) = check_input(&input, &SafetyPolicy::default());
         assert_eq!(total_safe_dampened, input.len());
     }
+
+    /// The old check without dampener: steps of 1 to 3 in the majority direction.
+    fn process_report(entries: &[isize]) -> bool {
+        let mut diff = diffs(entries);
+        if !majority_positive(&diff) {
+            diff = diff.iter().map(|x| x * -1).collect();
+        }
+        diff.iter().all(|x| (1..=3).contains(x))
+    }
+
+    /// The old brute force dampener: retry the report without each level in turn.
+    fn dampener_index_brute_force(entries: &[isize]) -> Option<usize> {
+        (0..entries.len()).find(|&rit| {
+            let mut parts2 = entries.to_vec();
+            parts2.remove(rit);
+            process_report(&parts2)
+        })
+    }
+
+    fn dampener_index(entries: &[isize]) -> Option<usize> {
+        let outcome = SafetyPolicy::default().outcome(entries)?;
+        Some(outcome.removed.first().copied().unwrap_or(0))
+    }
+
+    #[test]
+    fn check_dampener_index() {
+        let input = vec![
+            vec![7, 6, 4, 2, 1],
+            vec![1, 2, 7, 8, 9],
+            vec![9, 7, 6, 2, 1],
+            vec![1, 3, 2, 4, 5],
+            vec![8, 6, 4, 4, 1],
+            vec![1, 3, 6, 7, 9],
+            vec![8, 7, 10, 12, 13],
+            vec![7, 7, 10, 12, 13],
+            vec![7, 10],
+            vec![2, 1, 5, 6],
+            vec![50, 51, 47, 46],
+            vec![1, 2, 15, 4],
+            vec![48, 46, 47, 49, 51, 54, 56],
+            vec![1, 1, 2, 3, 4, 5],
+            vec![1, 2, 3, 4, 5, 5],
+            vec![5, 1, 2, 3, 4, 5],
+            vec![1, 4, 3, 2, 1],
+            vec![1, 6, 7, 8, 9],
+            vec![1, 2, 3, 4, 3],
+            vec![9, 8, 7, 6, 7],
+            vec![1, 5, 9],
+        ];
+        for entries in input {
+            assert_eq!(
+                dampener_index(&entries),
+                dampener_index_brute_force(&entries),
+                "{:?}",
+                entries
+            );
+        }
+        assert_eq!(dampener_index(&[1, 3, 2, 4, 5]), Some(1));
+        assert_eq!(dampener_index(&[9, 8, 7, 6, 7]), Some(4));
+        assert_eq!(dampener_index(&[1, 2, 7, 8, 9]), None);
+    }
+
+    #[test]
+    fn check_dampener_on_input() {
+        for entries in read_input() {
+            let safe = process_report(&entries);
+            assert_eq!(SafetyPolicy::default().undampened().is_safe(&entries), safe);
+            if !safe {
+                assert_eq!(
+                    dampener_index(&entries),
+                    dampener_index_brute_force(&entries)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn check_policy() {
+        let increasing = SafetyPolicy {
+            direction: Direction::Increasing,
+            ..SafetyPolicy::default()
+        };
+        assert!(!increasing.is_safe(&[7, 6, 4, 2, 1]));
+        assert!(increasing.is_safe(&[1, 3, 6, 7, 9]));
+
+        let wide = SafetyPolicy {
+            steps: 1..=5,
+            max_removals: 0,
+            ..SafetyPolicy::default()
+        };
+        assert!(wide.is_safe(&[1, 2, 7, 8, 9]));
+
+        let plateau = SafetyPolicy {
+            plateaus: 1,
+            max_removals: 0,
+            ..SafetyPolicy::default()
+        };
+        assert!(plateau.is_safe(&[8, 6, 4, 4, 1]));
+        assert!(!plateau.is_safe(&[8, 6, 6, 4, 4, 1]));
+
+        let two = SafetyPolicy {
+            max_removals: 2,
+            ..SafetyPolicy::default()
+        };
+        let outcome = two.outcome(&[1, 2, 9, 10, 3, 4]).unwrap();
+        assert_eq!(outcome.removed, vec![2, 3]);
+        assert_eq!(outcome.direction, Direction::Increasing);
+        assert!(!SafetyPolicy::default().is_safe(&[1, 2, 9, 10, 3, 4]));
+    }
+
+    /// Whether some set of at most `k` removed levels makes the report safe, trying them all.
+    fn brute_force_safe(policy: &SafetyPolicy, entries: &[isize], k: usize) -> bool {
+        let strict = policy.undampened();
+        if strict.is_safe(entries) {
+            return true;
+        }
+        k > 0
+            && (0..entries.len()).any(|i| {
+                let mut rest = entries.to_vec();
+                rest.remove(i);
+                brute_force_safe(policy, &rest, k - 1)
+            })
+    }
+
+    #[test]
+    fn check_dp_against_brute_force() {
+        for (k, plateaus) in [(1, 0), (1, 1), (2, 0), (2, 1), (3, 2)] {
+            let policy = SafetyPolicy {
+                max_removals: k,
+                plateaus,
+                ..SafetyPolicy::default()
+            };
+            for entries in read_input().iter().take(200) {
+                assert_eq!(
+                    policy.is_safe(entries),
+                    brute_force_safe(&policy, entries, k),
+                    "{:?} {:?}",
+                    policy,
+                    entries
+                );
+                for &sign in &[1, -1] {
+                    assert_eq!(
+                        policy.removals_directed(entries, sign).map(|r| r.len()),
+                        policy.removals_dp(entries, sign).map(|r| r.len()),
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn check_diagnostics() {
+        let input = vec![
+            vec![7, 6, 4, 2, 1],
+            vec![1, 2, 7, 8, 9],
+            vec![1, 3, 2, 4, 5],
+            vec![8, 6, 4, 4, 1],
+        ];
+        let diags = diagnose(&input, &SafetyPolicy::default());
+        let status: Vec<Status> = diags.iter().map(|d| d.status).collect();
+        assert_eq!(
+            status,
+            vec![
+                Status::Safe,
+                Status::Unsafe,
+                Status::SafeAfterDampening,
+                Status::SafeAfterDampening
+            ]
+        );
+        assert_eq!(diags[0].direction, Direction::Decreasing);
+        assert_eq!(diags[0].first_bad, None);
+        assert_eq!(diags[1].first_bad, Some((1, 5)));
+        assert!(diags[1].removed.is_empty());
+        assert_eq!(diags[2].direction, Direction::Increasing);
+        assert_eq!(diags[2].first_bad, Some((1, -1)));
+        assert_eq!(diags[2].removed, vec![(1, 3)]);
+        assert_eq!(diags[3].first_bad, Some((2, 0)));
+        assert_eq!(diags[3].removed, vec![(2, 4)]);
+
+        assert_eq!(diags[1].csv_row(), "1,unsafe,increasing,1,5,,");
+        assert_eq!(diags[2].csv_row(), "2,dampened,increasing,1,-1,1,3");
+        assert_eq!(
+            diags[2].table_row(),
+            "     2  dampened  increasing     1     -1  3 at 1"
+        );
+
+        // as many steps up as down, but removing the 5 makes it decreasing
+        let diags = diagnose(&[vec![5, 6, 4]], &SafetyPolicy::default());
+        assert_eq!(diags[0].direction, Direction::Decreasing);
+        assert_eq!(diags[0].first_bad, Some((0, 1)));
+        assert_eq!(diags[0].removed, vec![(0, 5)]);
+    }
+
+    #[test]
+    fn check_explain() {
+        let policy = SafetyPolicy::default();
+        let expl = explain_report(0, &[7, 6, 4, 2, 1], &policy);
+        assert!(expl.safe && expl.safe_dampened);
+        assert_eq!(expl.reason, "all steps decreasing by 1 to 3");
+
+        let expl = explain_report(1, &[1, 2, 7, 8, 9], &policy);
+        assert!(!expl.safe_dampened);
+        assert_eq!(
+            expl.reason,
+            "step 5 from 2 to 7 at index 1, no single removal helps"
+        );
+
+        let expl = explain_report(3, &[1, 3, 2, 4, 5], &policy);
+        assert!(!expl.safe && expl.safe_dampened);
+        assert_eq!(
+            expl.reason,
+            "step -1 from 3 to 2 at index 1, safe after removing level 3 at index 1"
+        );
+        assert_eq!(
+            expl.to_json(),
+            "{\"index\": 3, \"levels\": [1, 3, 2, 4, 5], \"safe\": false, \"safe_dampened\": true, \"reason\": \"step -1 from 3 to 2 at index 1, safe after removing level 3 at index 1\"}"
+        );
+    }
 }
@@ -1,75 +1,422 @@
+use std::ops::Range;
+
+mod stream;
+
 fn main() {
-    let s = std::fs::read_to_string("input").unwrap();
+    // `--extended` adds the `add(X)`, `sub(X)` and `reset()` instructions of variant dumps
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut set = InstructionSet::standard();
+    if args.iter().any(|a| a == "--extended") {
+        set.register("add", 1, |m, args| m.acc += args[0] as i64)
+            .register("sub", 1, |m, args| m.acc -= args[0] as i64)
+            .register("reset", 0, |m, _| m.acc = 0);
+    }
+
+    // `--stream` reads the input in chunks, for dumps too large for memory
+    if args.iter().any(|a| a == "--stream") {
+        let file = std::fs::File::open("input").unwrap();
+        let (resq1, resq2) = stream::run_stream(file, &set, 1 << 20).unwrap();
+        println!("Result Q1: {}", resq1);
+        println!("Result Q2: {}", resq2);
+        return;
+    }
+
+    let s = std::fs::read("input").unwrap();
+    let instructions = parse(&s, &set);
 
-    let resq1 = process_string(&s);
+    let resq1 = part1(&instructions, &set);
     println!("Result Q1: {}", resq1);
 
-    let resq2 = filter_and_process(&s);
+    let resq2 = part2(&instructions, &set);
     println!("Result Q2: {}", resq2);
+
+    // Optional `--listing`: every instruction and near miss with its byte offset
+    if args.iter().any(|a| a == "--listing") {
+        println!(
+            "{:>8}  {:<8}  {:<16}  product / reason",
+            "offset", "status", "text"
+        );
+        for entry in listing(&s, &set) {
+            println!("{}", entry.table_row());
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    /// Letters, `_` and `'`, e.g. `xmul` or `don't`.
+    Word,
+    Number,
+    LParen,
+    RParen,
+    Comma,
+    /// Any other byte.
+    Other,
+}
+
+/// A token with its byte span in the input.
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    span: Range<usize>,
 }
 
-fn process_string(s: &str) -> u64 {
-    let mut result = 0;
-    let mut next_start = 0;
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b == b'\''
+}
 
-    loop {
-        let Some(start) = s[next_start..].find("mul(") else {
-            break;
+/// Split the input into tokens. Works on bytes, so the input does not need to be UTF-8.
+fn tokenize(s: &[u8]) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut pos = 0;
+    while pos < s.len() {
+        let start = pos;
+        let kind = match s[pos] {
+            b if is_word_byte(b) => {
+                while pos < s.len() && is_word_byte(s[pos]) {
+                    pos += 1;
+                }
+                TokenKind::Word
+            }
+            b if b.is_ascii_digit() => {
+                while pos < s.len() && s[pos].is_ascii_digit() {
+                    pos += 1;
+                }
+                TokenKind::Number
+            }
+            b'(' => TokenKind::LParen,
+            b')' => TokenKind::RParen,
+            b',' => TokenKind::Comma,
+            _ => TokenKind::Other,
         };
-        let start = start + next_start;
+        // the other tokens are a single byte
+        pos = pos.max(start + 1);
+        tokens.push(Token {
+            kind,
+            span: start..pos,
+        });
+    }
+    tokens
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Instruction {
+    Mul(u64, u64),
+    Do,
+    Dont,
+    /// A registered instruction: its index in the instruction set, and its operands.
+    Custom(usize, Vec<u64>),
+}
+
+/// State of the machine running the instructions.
+#[derive(Debug, Clone, PartialEq)]
+struct Machine {
+    enabled: bool,
+    acc: i64,
+}
+
+/// Effect of a registered instruction on the machine. Instructions only take effect while the
+/// machine is enabled.
+type Exec = fn(&mut Machine, &[u64]);
+
+#[derive(Debug, Clone, Copy)]
+enum OpKind {
+    Mul,
+    Do,
+    Dont,
+    Custom(Exec),
+}
+
+/// An instruction the parser knows, with its number of operands.
+#[derive(Debug, Clone)]
+struct Op {
+    name: String,
+    arity: usize,
+    kind: OpKind,
+}
+
+/// The instructions the parser recognises and the machine executes.
+#[derive(Debug, Clone)]
+struct InstructionSet {
+    ops: Vec<Op>,
+}
 
-        let Some(end) = s[start..].find(")") else {
-            break;
+impl InstructionSet {
+    /// `mul(X,Y)`, `do()` and `don't()`.
+    fn standard() -> InstructionSet {
+        let op = |name: &str, arity, kind| Op {
+            name: name.to_string(),
+            arity,
+            kind,
         };
-        let end = end + start;
+        InstructionSet {
+            ops: vec![
+                op("mul", 2, OpKind::Mul),
+                op("do", 0, OpKind::Do),
+                op("don't", 0, OpKind::Dont),
+            ],
+        }
+    }
+
+    /// Add an instruction `name(X,...)` with `arity` operands of 1 to 3 digits.
+    fn register(&mut self, name: &str, arity: usize, exec: Exec) -> &mut InstructionSet {
+        self.ops.push(Op {
+            name: name.to_string(),
+            arity,
+            kind: OpKind::Custom(exec),
+        });
+        self
+    }
+
+    /// Length of the longest possible instruction, e.g. 12 for `mul(123,456)`.
+    fn max_len(&self) -> usize {
+        self.ops
+            .iter()
+            .map(|op| op.name.len() + 2 + 4 * op.arity - op.arity.min(1))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn instruction(&self, op: usize, args: Vec<u64>) -> Instruction {
+        match self.ops[op].kind {
+            OpKind::Mul => Instruction::Mul(args[0], args[1]),
+            OpKind::Do => Instruction::Do,
+            OpKind::Dont => Instruction::Dont,
+            OpKind::Custom(_) => Instruction::Custom(op, args),
+        }
+    }
+}
+
+impl Machine {
+    fn new() -> Machine {
+        Machine {
+            enabled: true,
+            acc: 0,
+        }
+    }
+
+    fn execute(&mut self, instruction: &Instruction, set: &InstructionSet) {
+        match instruction {
+            Instruction::Do => self.enabled = true,
+            Instruction::Dont => self.enabled = false,
+            _ if !self.enabled => (),
+            Instruction::Mul(a, b) => self.acc += (a * b) as i64,
+            Instruction::Custom(op, args) => match set.ops[*op].kind {
+                OpKind::Custom(exec) => exec(self, args),
+                _ => unreachable!("{} is not a registered instruction", set.ops[*op].name),
+            },
+        }
+    }
+
+    fn run<'a>(
+        &mut self,
+        instructions: impl IntoIterator<Item = &'a Parsed>,
+        set: &InstructionSet,
+    ) {
+        for p in instructions {
+            self.execute(&p.instruction, set);
+        }
+    }
+}
+
+/// An instruction with the byte span it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+struct Parsed {
+    instruction: Instruction,
+    span: Range<usize>,
+}
+
+/// Recognise exactly the instructions of the set, e.g. `mul(X,Y)` with 1 to 3 digit operands,
+/// `do()` and `don't()`.
+///
+/// An instruction name may be the end of a longer word, as in `xmul(2,4)` or `undo()`.
+fn parse(s: &[u8], set: &InstructionSet) -> Vec<Parsed> {
+    scan(s, set).0
+}
 
-        let in_between: Vec<&str> = s[start + 4..end].split(",").collect();
-        if in_between.len() != 2 {
-            next_start = start + 1;
+/// An instruction name that is not followed by a valid call, e.g. `mul[3,7]`.
+#[derive(Debug, Clone, PartialEq)]
+struct Rejected {
+    /// From the instruction name to the token that broke the call.
+    span: Range<usize>,
+    reason: String,
+}
+
+/// Like `parse`, but also return the near misses.
+fn scan(s: &[u8], set: &InstructionSet) -> (Vec<Parsed>, Vec<Rejected>) {
+    let tokens = tokenize(s);
+    let mut parsed = vec![];
+    let mut rejected = vec![];
+    for (it, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Word {
             continue;
-        } else {
-            let Ok(a) = in_between[0].parse::<u64>() else {
-                next_start = start + 1;
-                continue;
-            };
-            let Ok(b) = in_between[1].parse::<u64>() else {
-                next_start = start + 1;
+        }
+        let word = &s[token.span.clone()];
+        for (ind, op) in set.ops.iter().enumerate() {
+            if !word.ends_with(op.name.as_bytes()) {
                 continue;
-            };
-            result += a * b;
-            next_start = end + 1;
+            }
+            let start = token.span.end - op.name.len();
+            match parse_call(s, &tokens[it + 1..], op.arity) {
+                Ok((args, end)) => parsed.push(Parsed {
+                    instruction: set.instruction(ind, args),
+                    span: start..end,
+                }),
+                Err((reason, end)) => rejected.push(Rejected {
+                    span: start..end.max(token.span.end),
+                    reason,
+                }),
+            }
         }
     }
+    (parsed, rejected)
+}
 
-    result
+/// Parse `(X,Y,...)` with `arity` operands of 1 to 3 digits from the start of `tokens`, returning
+/// the operands and the end of the closing parenthesis. On failure, return why and the end of
+/// the offending token.
+fn parse_call(
+    s: &[u8],
+    tokens: &[Token],
+    arity: usize,
+) -> Result<(Vec<u64>, usize), (String, usize)> {
+    let mut tokens = tokens.iter();
+    let mut expect = |kind, what| match tokens.next() {
+        Some(t) if t.kind == kind => Ok(t),
+        Some(t) => Err((
+            format!(
+                "expected {}, found `{}`",
+                what,
+                printable(&s[t.span.clone()])
+            ),
+            t.span.end,
+        )),
+        None => Err((format!("expected {}, found end of input", what), s.len())),
+    };
+
+    expect(TokenKind::LParen, "`(`")?;
+    let mut args = vec![];
+    for it in 0..arity {
+        if it > 0 {
+            expect(TokenKind::Comma, "`,`")?;
+        }
+        let num = expect(TokenKind::Number, "an operand")?;
+        let digits = std::str::from_utf8(&s[num.span.clone()]).unwrap();
+        if digits.len() > 3 {
+            return Err((
+                format!("operand {} has more than 3 digits", digits),
+                num.span.end,
+            ));
+        }
+        args.push(digits.parse().unwrap());
+    }
+    let close = expect(TokenKind::RParen, "`)`")?;
+    Ok((args, close.span.end))
 }
 
-fn filter_and_process(s: &str) -> u64 {
-    let mut result = 0;
+/// Bytes as text, with invalid UTF-8 replaced and control characters escaped.
+fn printable(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .chars()
+        .map(|c| {
+            if c.is_control() {
+                c.escape_default().to_string()
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
 
-    let mut next_start = 0;
+/// One line of the disassembly listing: an instruction or a near miss.
+#[derive(Debug, Clone, PartialEq)]
+struct ListingEntry {
+    offset: usize,
+    text: String,
+    /// `enabled` or `disabled` when the instruction ran, or `rejected`.
+    status: &'static str,
+    /// Product of a `mul`, or why a near miss was rejected.
+    note: String,
+}
 
-    loop {
-        let Some(end) = s[next_start..].find("don't()") else {
-            result += process_string(&s[next_start..]);
-            break;
-        };
-        let end = end + next_start;
+impl ListingEntry {
+    fn table_row(&self) -> String {
+        format!(
+            "{:>8}  {:<8}  {:<16}  {}",
+            self.offset, self.status, self.text, self.note
+        )
+    }
+}
 
-        result += process_string(&s[next_start..end]);
+/// Every instruction with whether the machine was enabled when it ran, and every near miss,
+/// ordered by byte offset.
+fn listing(s: &[u8], set: &InstructionSet) -> Vec<ListingEntry> {
+    let (parsed, rejected) = scan(s, set);
+    let text = |span: &Range<usize>| printable(&s[span.clone()]);
 
-        let Some(ns) = s[end..].find("do()") else {
-            break;
+    let mut machine = Machine::new();
+    let mut entries = vec![];
+    for p in &parsed {
+        let status = if machine.enabled {
+            "enabled"
+        } else {
+            "disabled"
+        };
+        let note = match p.instruction {
+            Instruction::Mul(a, b) => (a * b).to_string(),
+            _ => String::new(),
         };
-        next_start = ns + end;
+        entries.push(ListingEntry {
+            offset: p.span.start,
+            text: text(&p.span),
+            status,
+            note,
+        });
+        machine.execute(&p.instruction, set);
+    }
+    for r in rejected {
+        entries.push(ListingEntry {
+            offset: r.span.start,
+            text: text(&r.span),
+            status: "rejected",
+            note: r.reason,
+        });
     }
-    result
+    entries.sort_by_key(|e| e.offset);
+    entries
+}
+
+/// Run all instructions, ignoring `do()` and `don't()`.
+fn part1(instructions: &[Parsed], set: &InstructionSet) -> i64 {
+    let mut machine = Machine::new();
+    let unconditional = instructions
+        .iter()
+        .filter(|p| !matches!(p.instruction, Instruction::Do | Instruction::Dont));
+    machine.run(unconditional, set);
+    machine.acc
+}
+
+/// Run all instructions, skipping those disabled by a preceding `don't()`.
+fn part2(instructions: &[Parsed], set: &InstructionSet) -> i64 {
+    let mut machine = Machine::new();
+    machine.run(instructions, set);
+    machine.acc
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn process_string(s: &str) -> i64 {
+        let set = InstructionSet::standard();
+        part1(&parse(s.as_bytes(), &set), &set)
+    }
+
+    fn filter_and_process(s: &str) -> i64 {
+        let set = InstructionSet::standard();
+        part2(&parse(s.as_bytes(), &set), &set)
+    }
+
     #[test]
     fn test_example_q1() {
         let s = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
@@ -81,4 +428,87 @@ mod tests {
         let s = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
         assert_eq!(filter_and_process(s), 48);
     }
+
+    #[test]
+    fn test_parse() {
+        let parsed = parse(b"xmul(2,4)&undo()?don't()", &InstructionSet::standard());
+        let spans: Vec<Range<usize>> = parsed.iter().map(|p| p.span.clone()).collect();
+        assert_eq!(spans, vec![1..9, 12..16, 17..24]);
+        assert_eq!(parsed[0].instruction, Instruction::Mul(2, 4));
+        assert_eq!(parsed[1].instruction, Instruction::Do);
+        assert_eq!(parsed[2].instruction, Instruction::Dont);
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert_eq!(process_string("mul(2,4mul(3,5)"), 15);
+        assert_eq!(process_string("mul( 2,4)"), 0);
+        assert_eq!(process_string("mul(1234,5)"), 0);
+        assert_eq!(process_string("mul(2,4,6)"), 0);
+        assert_eq!(process_string("do ()mul(2,4)"), 8);
+        let set = InstructionSet::standard();
+        assert_eq!(parse(b"don't(x)", &set).len(), 0);
+        assert_eq!(part1(&parse(b"\xff\xfe mul(2,4)", &set), &set), 8);
+    }
+
+    #[test]
+    fn test_listing() {
+        let s = b"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(1234,8)undo()?mul(8,5))";
+        let rows: Vec<String> = listing(s, &InstructionSet::standard())
+            .iter()
+            .map(|e| e.table_row())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                "       1  enabled   mul(2,4)          8",
+                "      10  rejected  mul[              expected `(`, found `[`",
+                "      20  enabled   don't()           ",
+                "      28  disabled  mul(5,5)          25",
+                "      37  rejected  mul(32,64]        expected `)`, found `]`",
+                "      48  rejected  mul(1234          operand 1234 has more than 3 digits",
+                "      61  disabled  do()              ",
+                "      66  enabled   mul(8,5)          40",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_len() {
+        let mut set = InstructionSet::standard();
+        assert_eq!(set.max_len(), "mul(123,456)".len());
+        set.register("reset", 0, |m, _| m.acc = 0);
+        assert_eq!(set.max_len(), "mul(123,456)".len());
+        set.register("fma", 3, |_, _| ());
+        assert_eq!(set.max_len(), "fma(123,456,789)".len());
+    }
+
+    #[test]
+    fn test_printable() {
+        assert_eq!(printable(b"don't\n\xff"), "don't\\n\u{fffd}");
+    }
+
+    #[test]
+    fn test_registered_instructions() {
+        let mut set = InstructionSet::standard();
+        set.register("add", 1, |m, args| m.acc += args[0] as i64)
+            .register("sub", 1, |m, args| m.acc -= args[0] as i64)
+            .register("reset", 0, |m, _| m.acc = 0);
+        let s = b"mul(2,4)sub(10)don't()add(100)do()add(5)xreset()mul(3,3)";
+        let parsed = parse(s, &set);
+        assert_eq!(parsed.len(), 8);
+        assert_eq!(parsed[1].instruction, Instruction::Custom(4, vec![10]));
+
+        let mut machine = Machine::new();
+        machine.run(&parsed[..5], &set);
+        assert_eq!(
+            machine,
+            Machine {
+                enabled: true,
+                acc: -2
+            }
+        );
+        assert_eq!(part2(&parsed, &set), 9);
+        assert_eq!(part1(&parsed[..6], &set), 103);
+    }
 }
@@ -0,0 +1,101 @@
+//! Streaming scanner for memory dumps too large to read at once.
+//!
+//! The input is read in chunks into a buffer that keeps the tail of the previous chunk. Whether
+//! bytes form an instruction only depends on the bytes from the instruction name onwards, and an
+//! instruction is at most `InstructionSet::max_len` bytes long. So every instruction starting at
+//! least that far before the end of the buffer is final, and everything before that point can be
+//! dropped. Instructions split between two chunks are found in the next round.
+use std::io::{self, Read};
+
+use crate::{parse, Instruction, InstructionSet, Machine, Parsed};
+
+/// Call `f` for every instruction in the input, in order, with spans counted from the start of
+/// the input.
+pub fn scan_stream(
+    mut reader: impl Read,
+    set: &InstructionSet,
+    chunk_size: usize,
+    mut f: impl FnMut(Parsed),
+) -> io::Result<()> {
+    let keep = set.max_len();
+    let mut buf: Vec<u8> = vec![];
+    // offset of `buf[0]` in the input
+    let mut base = 0;
+    let mut chunk = vec![0; chunk_size];
+    loop {
+        let read = reader.read(&mut chunk)?;
+        let done = read == 0;
+        buf.extend_from_slice(&chunk[..read]);
+
+        // at the end of the input everything is final
+        let cut = if done {
+            buf.len()
+        } else {
+            buf.len().saturating_sub(keep)
+        };
+        for mut p in parse(&buf, set).into_iter().filter(|p| p.span.start < cut) {
+            p.span = p.span.start + base..p.span.end + base;
+            f(p);
+        }
+        if done {
+            return Ok(());
+        }
+        buf.drain(..cut);
+        base += cut;
+    }
+}
+
+/// Results of both parts, like `part1` and `part2` on the whole input.
+pub fn run_stream(
+    reader: impl Read,
+    set: &InstructionSet,
+    chunk_size: usize,
+) -> io::Result<(i64, i64)> {
+    let mut unconditional = Machine::new();
+    let mut conditional = Machine::new();
+    scan_stream(reader, set, chunk_size, |p| {
+        if !matches!(p.instruction, Instruction::Do | Instruction::Dont) {
+            unconditional.execute(&p.instruction, set);
+        }
+        conditional.execute(&p.instruction, set);
+    })?;
+    Ok((unconditional.acc, conditional.acc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{part1, part2};
+
+    fn in_memory(s: &[u8], set: &InstructionSet) -> (i64, i64) {
+        let parsed = parse(s, set);
+        (part1(&parsed, set), part2(&parsed, set))
+    }
+
+    #[test]
+    fn test_matches_in_memory() {
+        let s = std::fs::read("input").unwrap();
+        let set = InstructionSet::standard();
+        for chunk_size in [1, 2, 7, 13, 64, 4096] {
+            assert_eq!(
+                run_stream(&s[..], &set, chunk_size).unwrap(),
+                in_memory(&s, &set)
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_instructions() {
+        let s =
+            b"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))\xff\xfe";
+        let mut set = InstructionSet::standard();
+        set.register("reset", 0, |m, _| m.acc = 0);
+        let whole = parse(s, &set);
+        for chunk_size in 1..20 {
+            let mut streamed = vec![];
+            scan_stream(&s[..], &set, chunk_size, |p| streamed.push(p)).unwrap();
+            assert_eq!(streamed, whole, "chunk size {}", chunk_size);
+        }
+        assert_eq!(run_stream(&s[..], &set, 3).unwrap(), (161, 48));
+    }
+}
@@ -1,10 +1,16 @@
 use std::io::{self, BufRead};
 
+use search::WordSearch;
+use template::Template;
+
+mod render;
+mod search;
+mod template;
+
 const PAT1: &str = "XMAS";
-const PAT2: &str = "SAMX";
 
-const PAT3: &str = "MAS";
-const PAT4: &str = "SAM";
+/// Two `MAS` crossing at the `A`, each readable in both directions.
+const X_MAS: &str = "# rotate\nM.S\n.A.\nM.S";
 
 fn main() {
     let s = std::fs::File::open("input").unwrap();
@@ -17,104 +23,86 @@ fn main() {
 
     let res2 = part2(&data);
     println!("Part 2: {}", res2);
-}
 
-fn part1(s: &Vec<String>) -> u64 {
-    let mut data: Vec<Vec<String>> = vec![];
-    for i in s {
-        let mut row: Vec<String> = vec![];
-        for j in i.chars() {
-            row.push(j.into());
+    // Optional `--words W1,W2,...`: every occurrence of the given words, crossing the edges of
+    // the grid with `--wrap`
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let wrap = args.iter().any(|a| a == "--wrap");
+    if let Some(pos) = args.iter().position(|a| a == "--words") {
+        let words = args
+            .get(pos + 1)
+            .expect("--words needs a comma separated list");
+        let search = WordSearch::new(words.split(',')).wrapping(wrap);
+        println!("{:>5}  {:>5}  {:<9}  word", "row", "col", "direction");
+        for m in search.find(&Grid::from_lines(&data)) {
+            println!("{}", m.table_row());
         }
-        data.push(row);
     }
 
-    let mut cnt = 0;
-
-    // search each line forward, backward
-    for line in &data {
-        for it in 0..line.len() - 3 {
-            let cmp = line[it..it + 4].join("");
-            if cmp == PAT1 || cmp == PAT2 {
-                cnt += 1;
-            }
+    // Optional `--template FILE`: every match of a shape template, see `template.rs`
+    if let Some(pos) = args.iter().position(|a| a == "--template") {
+        let path = args.get(pos + 1).expect("--template needs a file");
+        let text = std::fs::read_to_string(path).unwrap();
+        let template = Template::parse(&text).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        let found = template.find(&Grid::from_lines(&data));
+        println!("{:>5}  {:>5}  {:>7}", "row", "col", "variant");
+        for m in &found {
+            println!("{}", m.table_row());
         }
+        println!("{} matches", found.len());
     }
 
-    // search top to bottom
-    for chit in 0..data[0].len() {
-        for lit in 0..data.len() - 3 {
-            let mut cmp = ["", "", "", ""];
-            for jt in 0..4 {
-                cmp[jt] = &data[jt + lit][chit]
-            }
-            let cmp = cmp.join("");
-            if cmp == PAT1 || cmp == PAT2 {
-                cnt += 1;
-            }
-        }
-    }
-
-    // search diagonals top left to bottom right
-    for chit in 0..data[0].len() - 3 {
-        for lit in 0..data.len() - 3 {
-            let mut cmp = ["", "", "", ""];
-            for jt in 0..4 {
-                cmp[jt] = &data[jt + lit][chit + jt]
-            }
-            let cmp = cmp.join("");
-            if cmp == PAT1 || cmp == PAT2 {
-                cnt += 1;
-            }
-        }
+    // Optional `--show part1|part2 [--colour]`: the grid with only the matched letters, part 1
+    // also with `--wrap`
+    if let Some(pos) = args.iter().position(|a| a == "--show") {
+        let grid = Grid::from_lines(&data);
+        let cells: Vec<Vec<(usize, usize)>> = match args.get(pos + 1).map(|a| a.as_str()) {
+            Some("part1") => WordSearch::new([PAT1])
+                .wrapping(wrap)
+                .find(&grid)
+                .iter()
+                .map(|m| m.cells())
+                .collect(),
+            Some("part2") => Template::parse(X_MAS)
+                .unwrap()
+                .find(&grid)
+                .into_iter()
+                .map(|m| m.cells)
+                .collect(),
+            _ => panic!("--show needs `part1` or `part2`"),
+        };
+        let colour = args.iter().any(|a| a == "--colour");
+        print!("{}", render::render(&grid, &cells, colour));
     }
+}
 
-    // search diagonals top right to bottom left
-    for chit in 3..data[0].len() {
-        for lit in 0..data.len() - 3 {
-            let mut cmp = ["", "", "", ""];
-            for jt in 0..4 {
-                cmp[jt] = &data[jt + lit][chit - jt]
-            }
-            let cmp = cmp.join("");
-            if cmp == PAT1 || cmp == PAT2 {
-                cnt += 1;
-            }
-        }
-    }
-    cnt
+/// Letters of the puzzle, one row per line. Rows may differ in length.
+pub struct Grid {
+    rows: Vec<Vec<char>>,
 }
 
-fn part2(s: &Vec<String>) -> u64 {
-    let mut data: Vec<Vec<String>> = vec![];
-    for i in s {
-        let mut row: Vec<String> = vec![];
-        for j in i.chars() {
-            row.push(j.into());
+impl Grid {
+    pub fn from_lines<S: AsRef<str>>(lines: &[S]) -> Grid {
+        Grid {
+            rows: lines.iter().map(|l| l.as_ref().chars().collect()).collect(),
         }
-        data.push(row);
     }
 
-    let mut cnt = 0;
-
-    for lit in 1..data.len() - 1 {
-        for chit in 1..data[lit].len() - 1 {
-            let mut cmp1 = ["", "", ""];
-            let mut cmp2 = ["", "", ""];
-            for it in 0..=2 {
-                cmp1[it] = &data[lit - 1 + it][chit + 1 - it];
-                cmp2[it] = &data[lit - 1 + it][chit - 1 + it];
-            }
-            let cmp1 = cmp1.join("");
-            let cmp2 = cmp2.join("");
-            if (cmp1 == PAT3 || cmp1 == PAT4) && (cmp2 == PAT3 || cmp2 == PAT4) {
-                println!("cmp1 {}, cmp2 {}, lit {}, chit {}", cmp1, cmp2, lit, chit);
-                cnt += 1;
-            }
-        }
+    /// Letter at `(row, col)`, or `None` outside the grid.
+    pub fn get(&self, row: isize, col: isize) -> Option<char> {
+        let row = self.rows.get(usize::try_from(row).ok()?)?;
+        row.get(usize::try_from(col).ok()?).copied()
     }
+}
 
-    cnt
+fn part1(s: &[String]) -> u64 {
+    // every direction is searched, so the reversed word is found as well
+    WordSearch::new([PAT1]).count(&Grid::from_lines(s)) as u64
+}
+
+fn part2(s: &[String]) -> u64 {
+    let x_mas = Template::parse(X_MAS).unwrap();
+    x_mas.count(&Grid::from_lines(s)) as u64
 }
 
 #[cfg(test)]
@@ -152,6 +140,24 @@ mod tests {
         assert_eq!(part1(&s), 12);
     }
 
+    #[test]
+    fn test_part1_ragged() {
+        assert_eq!(part1(&[]), 0);
+        assert_eq!(part1(&["XMA".into(), "".into()]), 0);
+        let s: Vec<String> = vec!["XMASAMX".into(), "M".into(), "AA".into(), "S.S".into()];
+        // both ways along the first row and down the first column; the short second row cuts
+        // off the diagonals
+        assert_eq!(part1(&s), 3);
+    }
+
+    #[test]
+    fn test_part2_tiny() {
+        assert_eq!(part2(&[]), 0);
+        assert_eq!(part2(&["MS".into(), "A".into()]), 0);
+        let s: Vec<String> = vec!["M.S..".into(), ".A".into(), "M.S".into()];
+        assert_eq!(part2(&s), 1);
+    }
+
     #[test]
     fn test_part2() {
         let s: Vec<String> = vec![
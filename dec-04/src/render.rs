@@ -0,0 +1,107 @@
+//! Printing the grid with only the matched letters, like the illustrations in the puzzle.
+use crate::Grid;
+
+/// Foreground colours given to the matches in turn.
+const COLOURS: [u8; 6] = [31, 32, 33, 34, 35, 36];
+
+/// The grid with every cell outside of `matches` shown as `.`. Each match is given by its cells.
+/// With `colour`, the letters of each match are coloured with ANSI escapes; a cell covered by
+/// several matches gets the colour of the first one.
+pub fn render(grid: &Grid, matches: &[Vec<(usize, usize)>], colour: bool) -> String {
+    // index of the first match covering each cell
+    let mut owner: Vec<Vec<Option<usize>>> =
+        grid.rows.iter().map(|row| vec![None; row.len()]).collect();
+    for (ind, cells) in matches.iter().enumerate() {
+        for &(row, col) in cells {
+            if let Some(cell) = owner.get_mut(row).and_then(|r| r.get_mut(col)) {
+                cell.get_or_insert(ind);
+            }
+        }
+    }
+
+    let mut out = String::new();
+    for (row, line) in grid.rows.iter().enumerate() {
+        for (col, ch) in line.iter().enumerate() {
+            match owner[row][col] {
+                None => out.push('.'),
+                Some(ind) if colour => {
+                    let code = COLOURS[ind % COLOURS.len()];
+                    out += &format!("\x1b[1;{}m{}\x1b[0m", code, ch);
+                }
+                Some(_) => out.push(*ch),
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::WordSearch;
+    use crate::template::Template;
+    use crate::X_MAS;
+
+    const EXAMPLE: [&str; 10] = [
+        "MMMSXXMASM",
+        "MSAMXMSMSA",
+        "AMXSXMAAMM",
+        "MSAMASMSMX",
+        "XMASAMXAMM",
+        "XXAMMXXAMA",
+        "SMSMSASXSS",
+        "SAXAMASAAA",
+        "MAMMMXMMMM",
+        "MXMXAXMASX",
+    ];
+
+    #[test]
+    fn test_part1_illustration() {
+        let g = Grid::from_lines(&EXAMPLE);
+        let search = WordSearch::new(["XMAS"]);
+        let cells: Vec<_> = search.find(&g).iter().map(|m| m.cells()).collect();
+        assert_eq!(cells.len(), 18);
+        let expected = "\
+....XXMAS.
+.SAMXMS...
+...S..A...
+..A.A.MS.X
+XMASAMX.MM
+X.....XA.A
+S.S.S.S.SS
+.A.A.A.A.A
+..M.M.M.MM
+.X.X.XMASX
+";
+        assert_eq!(render(&g, &cells, false), expected);
+    }
+
+    #[test]
+    fn test_part2_illustration() {
+        let g = Grid::from_lines(&EXAMPLE);
+        let x_mas = Template::parse(X_MAS).unwrap();
+        let cells: Vec<_> = x_mas.find(&g).into_iter().map(|m| m.cells).collect();
+        assert_eq!(cells.len(), 9);
+        let expected = "\
+.M.S......
+..A..MSMS.
+.M.S.MAA..
+..A.ASMSM.
+.M.S.M....
+..........
+S.S.S.S.S.
+.A.A.A.A..
+M.M.M.M.M.
+..........
+";
+        assert_eq!(render(&g, &cells, false), expected);
+    }
+
+    #[test]
+    fn test_colour() {
+        let g = Grid::from_lines(&["AB", "C"]);
+        let out = render(&g, &[vec![(0, 0)], vec![(0, 0), (1, 0), (5, 5)]], true);
+        assert_eq!(out, "\x1b[1;31mA\x1b[0m.\n\x1b[1;32mC\x1b[0m\n");
+    }
+}
@@ -0,0 +1,331 @@
+//! Multi-word search in all eight directions.
+//!
+//! The words are stored in a trie. From every cell the grid is walked in each direction while the
+//! letters read so far are a prefix of some word. So every word is found in the same single pass
+//! over the grid, and the cost per cell depends on the longest matching prefix rather than on the
+//! number of words.
+//!
+//! Rows may differ in length; cells past the end of a short row match nothing. In wrap-around
+//! mode the grid is a torus as wide as its longest row, and a word may cross the edges but must
+//! not use a cell twice.
+use std::collections::HashMap;
+
+use crate::Grid;
+
+/// One of the eight directions a word can be read in, clockwise from north.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::N,
+        Direction::NE,
+        Direction::E,
+        Direction::SE,
+        Direction::S,
+        Direction::SW,
+        Direction::W,
+        Direction::NW,
+    ];
+
+    /// Step as `(rows, columns)`, with rows growing downwards.
+    pub fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::N => (-1, 0),
+            Direction::NE => (-1, 1),
+            Direction::E => (0, 1),
+            Direction::SE => (1, 1),
+            Direction::S => (1, 0),
+            Direction::SW => (1, -1),
+            Direction::W => (0, -1),
+            Direction::NW => (-1, -1),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::N => "N",
+            Direction::NE => "NE",
+            Direction::E => "E",
+            Direction::SE => "SE",
+            Direction::S => "S",
+            Direction::SW => "SW",
+            Direction::W => "W",
+            Direction::NW => "NW",
+        }
+    }
+}
+
+/// A word found in the grid, read from `(row, col)` in `direction`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<'a> {
+    pub row: usize,
+    pub col: usize,
+    pub direction: Direction,
+    pub word: &'a str,
+    /// Height and width of the grid if the word may wrap around its edges.
+    wrap: Option<(usize, usize)>,
+}
+
+impl Match<'_> {
+    /// Cells covered by the word, from its first letter to its last.
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        let (dr, dc) = self.direction.delta();
+        (0..self.word.chars().count() as isize)
+            .map(|it| {
+                let (r, c) = (self.row as isize + it * dr, self.col as isize + it * dc);
+                match self.wrap {
+                    Some((height, width)) => (
+                        r.rem_euclid(height as isize) as usize,
+                        c.rem_euclid(width as isize) as usize,
+                    ),
+                    None => (r as usize, c as usize),
+                }
+            })
+            .collect()
+    }
+
+    pub fn table_row(&self) -> String {
+        format!(
+            "{:>5}  {:>5}  {:<9}  {}",
+            self.row,
+            self.col,
+            self.direction.name(),
+            self.word
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: HashMap<char, usize>,
+    /// Index of the word ending at this node.
+    word: Option<usize>,
+}
+
+/// A set of words to look for, searched for all at once.
+#[derive(Debug)]
+pub struct WordSearch {
+    words: Vec<String>,
+    /// Trie of all words, `nodes[0]` is the root.
+    nodes: Vec<Node>,
+    directions: Vec<Direction>,
+    wrap: bool,
+}
+
+impl WordSearch {
+    /// Search for `words` in all eight directions. Duplicates and empty words are ignored.
+    pub fn new<S: AsRef<str>>(words: impl IntoIterator<Item = S>) -> WordSearch {
+        let mut search = WordSearch {
+            words: vec![],
+            nodes: vec![Node::default()],
+            directions: Direction::ALL.to_vec(),
+            wrap: false,
+        };
+        for word in words {
+            search.insert(word.as_ref());
+        }
+        search
+    }
+
+    #[cfg(test)]
+    /// Only read words in the given directions.
+    pub fn directions(mut self, directions: &[Direction]) -> WordSearch {
+        self.directions = directions.to_vec();
+        self
+    }
+
+    /// Let words run across the edges of the grid, see the module documentation.
+    pub fn wrapping(mut self, wrap: bool) -> WordSearch {
+        self.wrap = wrap;
+        self
+    }
+
+    fn insert(&mut self, word: &str) {
+        if word.is_empty() {
+            return;
+        }
+        let mut node = 0;
+        for ch in word.chars() {
+            node = match self.nodes[node].children.get(&ch) {
+                Some(&next) => next,
+                None => {
+                    self.nodes.push(Node::default());
+                    let next = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(ch, next);
+                    next
+                }
+            };
+        }
+        if self.nodes[node].word.is_none() {
+            self.nodes[node].word = Some(self.words.len());
+            self.words.push(word.into());
+        }
+    }
+
+    #[cfg(test)]
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// Every occurrence of every word, ordered by start cell, then direction, then length.
+    pub fn find<'a>(&'a self, grid: &Grid) -> Vec<Match<'a>> {
+        let width = grid.rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        let wrap = self.wrap.then_some((grid.rows.len(), width));
+        let mut found = vec![];
+        for row in 0..grid.rows.len() {
+            for col in 0..grid.rows[row].len() {
+                for &direction in &self.directions {
+                    self.walk(grid, row, col, direction, wrap, &mut found);
+                }
+            }
+        }
+        found
+    }
+
+    /// Follow the trie from `(row, col)` in `direction` as long as the letters allow.
+    fn walk<'a>(
+        &'a self,
+        grid: &Grid,
+        row: usize,
+        col: usize,
+        direction: Direction,
+        wrap: Option<(usize, usize)>,
+        found: &mut Vec<Match<'a>>,
+    ) {
+        let (dr, dc) = direction.delta();
+        let (mut r, mut c) = (row as isize, col as isize);
+        let mut node = 0;
+        while let Some(&next) = grid
+            .get(r, c)
+            .and_then(|ch| self.nodes[node].children.get(&ch))
+        {
+            node = next;
+            if let Some(word) = self.nodes[node].word {
+                found.push(Match {
+                    row,
+                    col,
+                    direction,
+                    word: &self.words[word],
+                    wrap,
+                });
+            }
+            r += dr;
+            c += dc;
+            if let Some((height, width)) = wrap {
+                (r, c) = (r.rem_euclid(height as isize), c.rem_euclid(width as isize));
+                // every step moves to a new cell until the walk comes back to its start
+                if (r, c) == (row as isize, col as isize) {
+                    break;
+                }
+            }
+        }
+    }
+
+    pub fn count(&self, grid: &Grid) -> usize {
+        self.find(grid).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(lines: &[&str]) -> Grid {
+        Grid::from_lines(lines)
+    }
+
+    #[test]
+    fn test_directions() {
+        // "AB" read from the centre of a 3x3 grid of B's towards each neighbour
+        let g = grid(&["BBB", "BAB", "BBB"]);
+        let search = WordSearch::new(["AB"]);
+        let found = search.find(&g);
+        let dirs: Vec<Direction> = found.iter().map(|m| m.direction).collect();
+        assert_eq!(dirs, Direction::ALL);
+        assert!(found.iter().all(|m| (m.row, m.col) == (1, 1)));
+        assert_eq!(found[3].cells(), vec![(1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn test_many_words() {
+        let g = grid(&["CATS", "AXOX", "RXXG", "TEAR"]);
+        let search = WordSearch::new(["CAT", "CATS", "CART", "DOG", "TEA", "TEAR", "CAT", ""]);
+        assert_eq!(search.words().len(), 6);
+        let found: Vec<String> = search
+            .find(&g)
+            .iter()
+            .map(|m| format!("{} {} {} {}", m.row, m.col, m.direction.name(), m.word))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                "0 0 E CAT",
+                "0 0 E CATS",
+                "0 0 S CART",
+                "3 0 E TEA",
+                "3 0 E TEAR"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ragged_and_tiny() {
+        let search = WordSearch::new(["XMAS", "AB"]);
+        assert_eq!(search.count(&grid(&[])), 0);
+        assert_eq!(search.count(&grid(&["", "A"])), 0);
+        assert_eq!(search.count(&grid(&["X"])), 0);
+        // the column is cut short by the second row, the diagonal is not
+        let g = grid(&["XX", "M", "AA", "SSS"]);
+        let found = search.find(&g);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].cells(), vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(search.count(&grid(&["XMAS", "", "AB"])), 2);
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let g = grid(&["ASXM", "....", "...."]);
+        let search = WordSearch::new(["XMAS"]);
+        assert_eq!(search.count(&g), 0);
+        let search = search.wrapping(true);
+        let found = search.find(&g);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].cells(), vec![(0, 2), (0, 3), (0, 0), (0, 1)]);
+
+        // diagonally across the right and the bottom edge
+        let g = grid(&[".S.X.", "....M", "A...."]);
+        let found = search.find(&g);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].direction, Direction::SE);
+        assert_eq!(found[0].cells(), vec![(0, 3), (1, 4), (2, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn test_wrapping_no_reuse() {
+        // a word may not run around the torus onto its own first letter
+        let search = WordSearch::new(["AA", "AAA"]).wrapping(true);
+        assert_eq!(search.count(&grid(&["A"])), 0);
+        let found = search.find(&grid(&["AA"]));
+        assert!(found.iter().all(|m| m.word == "AA"));
+        // in a single row N and S come straight back, every other direction reaches the other cell
+        assert_eq!(found.len(), 12);
+    }
+
+    #[test]
+    fn test_restricted_directions() {
+        let g = grid(&["XMAS", "SAMX"]);
+        let search = WordSearch::new(["XMAS"]);
+        assert_eq!(search.count(&g), 2);
+        assert_eq!(search.directions(&[Direction::E]).count(&g), 1);
+    }
+}
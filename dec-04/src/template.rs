@@ -0,0 +1,238 @@
+//! Matching small 2D shapes, like the X of two `MAS` in part 2, against the grid.
+//!
+//! A template is written as rows of letters, with `.` for cells that match anything. An optional
+//! first line starting with `#` lists flags: `rotate` also matches the shape turned by 90, 180
+//! and 270 degrees, `reflect` also matches its mirror image. Orientations that look the same are
+//! only matched once, so a symmetric shape is not counted twice at the same place.
+//!
+//! ```text
+//! # rotate
+//! M.S
+//! .A.
+//! M.S
+//! ```
+use crate::Grid;
+
+/// Cells of one orientation of a template, `None` for wildcards.
+type Shape = Vec<Vec<Option<char>>>;
+
+#[derive(Debug)]
+pub struct Template {
+    /// Distinct orientations to match, the shape as written first.
+    variants: Vec<Shape>,
+}
+
+/// An occurrence of a template, with the top left corner of the matched orientation at
+/// `(row, col)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateMatch {
+    pub row: usize,
+    pub col: usize,
+    /// Index of the matched orientation, 0 for the shape as written.
+    pub variant: usize,
+    /// Grid cells matched by letters of the template, wildcards left out.
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl TemplateMatch {
+    pub fn table_row(&self) -> String {
+        format!("{:>5}  {:>5}  {:>7}", self.row, self.col, self.variant)
+    }
+}
+
+impl Template {
+    /// Parse a template in the format described in the module documentation.
+    pub fn parse(text: &str) -> Result<Template, String> {
+        let mut lines = text.lines().peekable();
+        let (mut rotate, mut reflect) = (false, false);
+        if let Some(flags) = lines.peek().and_then(|l| l.trim().strip_prefix('#')) {
+            for flag in flags.split_whitespace() {
+                match flag {
+                    "rotate" => rotate = true,
+                    "reflect" => reflect = true,
+                    _ => return Err(format!("line 1: unknown flag `{}`", flag)),
+                }
+            }
+            lines.next();
+        }
+        let shape: Shape = lines
+            .map(|l| {
+                l.trim_end()
+                    .chars()
+                    .map(|ch| if ch == '.' { None } else { Some(ch) })
+                    .collect()
+            })
+            .collect();
+        if shape.iter().flatten().all(|cell| cell.is_none()) {
+            return Err("template has no letters".into());
+        }
+        Ok(Template::new(shape, rotate, reflect))
+    }
+
+    fn new(shape: Shape, rotate: bool, reflect: bool) -> Template {
+        // pad ragged rows with wildcards, so the shape can be turned
+        let width = shape.iter().map(|r| r.len()).max().unwrap_or(0);
+        let shape: Shape = shape
+            .into_iter()
+            .map(|mut r| {
+                r.resize(width, None);
+                r
+            })
+            .collect();
+
+        let mut candidates = vec![shape];
+        if rotate {
+            for it in 0..3 {
+                let turned = rotated(&candidates[it]);
+                candidates.push(turned);
+            }
+        }
+        if reflect {
+            let mirrored: Vec<Shape> = candidates.iter().map(reflected).collect();
+            candidates.extend(mirrored);
+        }
+        let mut variants: Vec<Shape> = vec![];
+        for shape in candidates {
+            if !variants.contains(&shape) {
+                variants.push(shape);
+            }
+        }
+        Template { variants }
+    }
+
+    /// Number of distinct orientations that are matched.
+    #[cfg(test)]
+    pub fn num_variants(&self) -> usize {
+        self.variants.len()
+    }
+
+    /// Every place where some orientation of the template fits, ordered by position.
+    pub fn find(&self, grid: &Grid) -> Vec<TemplateMatch> {
+        let width = grid.rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        let mut found = vec![];
+        for row in 0..grid.rows.len() {
+            for col in 0..width {
+                for (variant, shape) in self.variants.iter().enumerate() {
+                    if let Some(cells) = fit(grid, shape, row, col) {
+                        found.push(TemplateMatch {
+                            row,
+                            col,
+                            variant,
+                            cells,
+                        });
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    pub fn count(&self, grid: &Grid) -> usize {
+        self.find(grid).len()
+    }
+}
+
+/// Cells matched by the letters of `shape` placed at `(row, col)`, if they all match.
+fn fit(grid: &Grid, shape: &Shape, row: usize, col: usize) -> Option<Vec<(usize, usize)>> {
+    let mut cells = vec![];
+    for (dr, line) in shape.iter().enumerate() {
+        for (dc, cell) in line.iter().enumerate() {
+            if let Some(ch) = cell {
+                let (r, c) = (row + dr, col + dc);
+                if grid.get(r as isize, c as isize) != Some(*ch) {
+                    return None;
+                }
+                cells.push((r, c));
+            }
+        }
+    }
+    Some(cells)
+}
+
+/// `shape` turned clockwise by 90 degrees.
+fn rotated(shape: &Shape) -> Shape {
+    let width = shape.first().map_or(0, |r| r.len());
+    (0..width)
+        .map(|c| shape.iter().rev().map(|r| r[c]).collect())
+        .collect()
+}
+
+/// `shape` mirrored left to right.
+fn reflected(shape: &Shape) -> Shape {
+    shape
+        .iter()
+        .map(|r| r.iter().rev().copied().collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [&str; 10] = [
+        "MMMSXXMASM",
+        "MSAMXMSMSA",
+        "AMXSXMAAMM",
+        "MSAMASMSMX",
+        "XMASAMXAMM",
+        "XXAMMXXAMA",
+        "SMSMSASXSS",
+        "SAXAMASAAA",
+        "MAMMMXMMMM",
+        "MXMXAXMASX",
+    ];
+
+    #[test]
+    fn test_variants() {
+        // the X is symmetric under reflection, so only its rotations are distinct
+        let x = Template::parse("# rotate reflect\nM.S\n.A.\nM.S").unwrap();
+        assert_eq!(x.num_variants(), 4);
+        let l = Template::parse("# rotate reflect\nX\nX\nXX").unwrap();
+        assert_eq!(l.num_variants(), 8);
+        let plus = Template::parse("# rotate\n.M\nMAS\n.S").unwrap();
+        assert_eq!(plus.num_variants(), 4);
+        assert_eq!(Template::parse("ab\ncd").unwrap().num_variants(), 1);
+    }
+
+    #[test]
+    fn test_x_mas() {
+        let g = Grid::from_lines(&EXAMPLE);
+        let x = Template::parse("# rotate\nM.S\n.A.\nM.S").unwrap();
+        let found = x.find(&g);
+        assert_eq!(found.len(), 9);
+        assert_eq!(
+            found[0],
+            TemplateMatch {
+                row: 0,
+                col: 1,
+                variant: 0,
+                cells: vec![(0, 1), (0, 3), (1, 2), (2, 1), (2, 3)],
+            }
+        );
+        // without rotations only the Xs with both M on the left are found
+        let left = Template::parse("M.S\n.A.\nM.S").unwrap();
+        assert!(left.count(&g) < 9);
+        assert_eq!(
+            found.iter().filter(|m| m.variant == 0).count(),
+            left.count(&g)
+        );
+    }
+
+    #[test]
+    fn test_other_shapes() {
+        let g = Grid::from_lines(&["XM.X", "MAS.", ".S.."]);
+        // a plus with MAS across and down, in any of its four orientations
+        let plus = Template::parse("# rotate\n.M.\nMAS\n.S.").unwrap();
+        assert_eq!(plus.count(&g), 1);
+        // an L of three letters, with the M in the corner
+        let l = Template::parse("# rotate reflect\nX.\nMA").unwrap();
+        assert_eq!(l.count(&g), 2);
+    }
+
+    #[test]
+    fn test_bad_templates() {
+        assert!(Template::parse("# spin\nXM").is_err());
+        assert!(Template::parse("..\n..").is_err());
+        assert!(Template::parse("").is_err());
+    }
+}
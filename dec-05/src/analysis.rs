@@ -0,0 +1,296 @@
+//! Consistency checks for a set of page ordering rules.
+//!
+//! The rules only need to be free of cycles between the pages of each single update. Cycles in
+//! the whole rule graph are reported too, but are harmless as long as no update contains all
+//! pages of one. Cycles are found per strongly connected component of the graph, each reported
+//! as the shortest cycle through the component's smallest page.
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::rules::RuleSet;
+
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    /// The rule on `line` repeats the one on line `first`.
+    Duplicate {
+        rule: (u64, u64),
+        line: usize,
+        first: usize,
+    },
+    /// The rule on `line` is the reverse of the one on line `other`.
+    Contradiction {
+        rule: (u64, u64),
+        line: usize,
+        other: usize,
+    },
+    /// Rules that lead from a page back to itself.
+    Cycle { chain: Vec<(u64, u64)> },
+    /// A cycle between the pages of one update, which then cannot be put in order.
+    UpdateCycle {
+        update: usize,
+        chain: Vec<(u64, u64)>,
+    },
+    /// A page of an update that no rule mentions, so its position is arbitrary.
+    Unmentioned { update: usize, page: u64 },
+}
+
+impl Problem {
+    pub fn description(&self) -> String {
+        let chain = |chain: &[(u64, u64)]| {
+            let rules: Vec<String> = chain.iter().map(|(a, b)| format!("{}|{}", a, b)).collect();
+            rules.join(" -> ")
+        };
+        match self {
+            Problem::Duplicate { rule, line, first } => format!(
+                "line {}: rule {}|{} duplicates line {}",
+                line, rule.0, rule.1, first
+            ),
+            Problem::Contradiction { rule, line, other } => format!(
+                "line {}: rule {}|{} contradicts {}|{} on line {}",
+                line, rule.0, rule.1, rule.1, rule.0, other
+            ),
+            Problem::Cycle { chain: c } => format!("cycle: {}", chain(c)),
+            Problem::UpdateCycle { update, chain: c } => {
+                format!("update {}: cycle {}", update, chain(c))
+            }
+            Problem::Unmentioned { update, page } => {
+                format!(
+                    "update {}: page {} is not mentioned by any rule",
+                    update, page
+                )
+            }
+        }
+    }
+}
+
+/// All problems with `rules` and `updates` as returned by `parse_input`. Rules are numbered by
+/// their line in the input, updates from 0 like in `explain`.
+pub fn check(rules: &[(u64, u64)], updates: &[Vec<u64>]) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    let mut seen: HashMap<(u64, u64), usize> = HashMap::new();
+    for (it, &rule) in rules.iter().enumerate() {
+        let line = it + 1;
+        if let Some(&first) = seen.get(&rule) {
+            problems.push(Problem::Duplicate { rule, line, first });
+            continue;
+        }
+        if let Some(&other) = seen.get(&(rule.1, rule.0)) {
+            problems.push(Problem::Contradiction { rule, line, other });
+        }
+        seen.insert(rule, line);
+    }
+
+    let set = RuleSet::new(rules);
+    let mut pages: Vec<u64> = rules.iter().flat_map(|&(a, b)| [a, b]).collect();
+    pages.sort_unstable();
+    pages.dedup();
+    for chain in cycles(&pages, &set) {
+        problems.push(Problem::Cycle { chain });
+    }
+
+    let mentioned: HashSet<u64> = pages.into_iter().collect();
+    for (update, line) in updates.iter().enumerate() {
+        for &page in line {
+            if !mentioned.contains(&page) {
+                problems.push(Problem::Unmentioned { update, page });
+            }
+        }
+        if set.sorted(line).is_none() {
+            let mut pages = line.clone();
+            pages.sort_unstable();
+            pages.dedup();
+            for chain in cycles(&pages, &set) {
+                problems.push(Problem::UpdateCycle { update, chain });
+            }
+        }
+    }
+    problems
+}
+
+/// One cycle for every strongly connected component of the rules between `pages`, which must be
+/// sorted and free of duplicates.
+fn cycles(pages: &[u64], set: &RuleSet) -> Vec<Vec<(u64, u64)>> {
+    let index: HashMap<u64, usize> = pages.iter().enumerate().map(|(i, &p)| (p, i)).collect();
+    let succ: Vec<Vec<usize>> = pages
+        .iter()
+        .map(|&a| {
+            let mut next: Vec<usize> = set
+                .after(a)
+                .filter_map(|b| index.get(&b).copied())
+                .collect();
+            next.sort_unstable();
+            next
+        })
+        .collect();
+
+    let mut found = vec![];
+    for component in components(&succ) {
+        let start = component[0];
+        let inside: HashSet<usize> = component.into_iter().collect();
+        if let Some(path) = shortest_cycle(&succ, start, &inside) {
+            found.push(
+                path.windows(2)
+                    .map(|w| (pages[w[0]], pages[w[1]]))
+                    .collect(),
+            );
+        }
+    }
+    found
+}
+
+/// Strongly connected components with Kosaraju's algorithm, each sorted, ordered by their
+/// smallest node.
+fn components(succ: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let n = succ.len();
+    let mut pred: Vec<Vec<usize>> = vec![vec![]; n];
+    for (a, next) in succ.iter().enumerate() {
+        for &b in next {
+            pred[b].push(a);
+        }
+    }
+
+    // nodes in order of finishing a depth first search
+    let mut finished = Vec::with_capacity(n);
+    let mut visited = vec![false; n];
+    for root in 0..n {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut stack = vec![(root, 0)];
+        while let Some((node, edge)) = stack.pop() {
+            if let Some(&next) = succ[node].get(edge) {
+                stack.push((node, edge + 1));
+                if !visited[next] {
+                    visited[next] = true;
+                    stack.push((next, 0));
+                }
+            } else {
+                finished.push(node);
+            }
+        }
+    }
+
+    let mut component = vec![usize::MAX; n];
+    let mut found: Vec<Vec<usize>> = vec![];
+    for &root in finished.iter().rev() {
+        if component[root] != usize::MAX {
+            continue;
+        }
+        let mut members = vec![root];
+        component[root] = found.len();
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            for &prev in &pred[node] {
+                if component[prev] == usize::MAX {
+                    component[prev] = found.len();
+                    members.push(prev);
+                    stack.push(prev);
+                }
+            }
+        }
+        members.sort_unstable();
+        found.push(members);
+    }
+    found.sort_unstable();
+    found
+}
+
+/// Nodes of the shortest way from `start` back to itself without leaving `inside`, both ends
+/// included.
+fn shortest_cycle(
+    succ: &[Vec<usize>],
+    start: usize,
+    inside: &HashSet<usize>,
+) -> Option<Vec<usize>> {
+    let mut parent: HashMap<usize, usize> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for &next in &succ[node] {
+            if next == start {
+                let mut path = vec![start, node];
+                while *path.last().unwrap() != start {
+                    path.push(parent[path.last().unwrap()]);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if inside.contains(&next) && !parent.contains_key(&next) {
+                parent.insert(next, node);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, tests::TDAT};
+
+    #[test]
+    fn test_consistent() {
+        let (rules, updates) = parse_input(TDAT);
+        assert_eq!(check(&rules, &updates), vec![]);
+    }
+
+    #[test]
+    fn test_problems() {
+        let rules = [
+            (1, 2),
+            (2, 3),
+            (1, 2),
+            (3, 1),
+            (4, 5),
+            (5, 4),
+            (6, 6),
+            (3, 7),
+        ];
+        let updates = vec![vec![1, 2, 7], vec![3, 1, 2, 9], vec![4, 5]];
+        let descriptions: Vec<String> = check(&rules, &updates)
+            .iter()
+            .map(|p| p.description())
+            .collect();
+        assert_eq!(
+            descriptions,
+            vec![
+                "line 3: rule 1|2 duplicates line 1",
+                "line 6: rule 5|4 contradicts 4|5 on line 5",
+                "cycle: 1|2 -> 2|3 -> 3|1",
+                "cycle: 4|5 -> 5|4",
+                "cycle: 6|6",
+                "update 1: page 9 is not mentioned by any rule",
+                "update 1: cycle 1|2 -> 2|3 -> 3|1",
+                "update 2: cycle 4|5 -> 5|4",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shortest_cycle() {
+        // 1 -> 2 -> 3 -> 4 -> 1 with a shortcut 2 -> 4
+        let rules = [(1, 2), (2, 3), (3, 4), (4, 1), (2, 4)];
+        let problems = check(&rules, &[vec![1, 2, 3, 4], vec![1, 2, 3]]);
+        assert_eq!(
+            problems,
+            vec![
+                Problem::Cycle {
+                    chain: vec![(1, 2), (2, 4), (4, 1)]
+                },
+                Problem::UpdateCycle {
+                    update: 0,
+                    chain: vec![(1, 2), (2, 4), (4, 1)]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_input_updates_acyclic() {
+        let s = std::fs::read_to_string("input").unwrap();
+        let (rules, updates) = parse_input(&s);
+        let problems = check(&rules, &updates);
+        assert!(problems.iter().all(|p| matches!(p, Problem::Cycle { .. })));
+    }
+}
@@ -0,0 +1,72 @@
+//! Graphviz export of the page ordering rules.
+use std::collections::HashSet;
+
+use crate::rules::RuleSet;
+use crate::validation::violations;
+
+/// The rules as a DOT digraph with an edge `x -> y` for every rule `x|y`, in input order and
+/// without duplicates. With an `update`, only rules between its pages are included, its pages
+/// are listed in update order, and the rules the update breaks are drawn in red.
+pub fn to_dot(rules: &[(u64, u64)], update: Option<&[u64]>) -> String {
+    let mut out = String::from("digraph rules {\n");
+    let mut broken: HashSet<(u64, u64)> = HashSet::new();
+    if let Some(update) = update {
+        for page in update {
+            out += &format!("    {};\n", page);
+        }
+        let set = RuleSet::new(rules);
+        broken = violations(&set, update)
+            .iter()
+            .map(|v| (v.x, v.y))
+            .collect();
+    }
+
+    let mut seen = HashSet::new();
+    for &(x, y) in rules {
+        if update.is_some_and(|u| !u.contains(&x) || !u.contains(&y)) || !seen.insert((x, y)) {
+            continue;
+        }
+        if broken.contains(&(x, y)) {
+            out += &format!("    {} -> {} [color=red];\n", x, y);
+        } else {
+            out += &format!("    {} -> {};\n", x, y);
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, tests::TDAT};
+
+    #[test]
+    fn test_all_rules() {
+        let dot = to_dot(&[(1, 2), (2, 3), (1, 2)], None);
+        assert_eq!(dot, "digraph rules {\n    1 -> 2;\n    2 -> 3;\n}\n");
+    }
+
+    #[test]
+    fn test_update() {
+        let (rules, updates) = parse_input(TDAT);
+        let dot = to_dot(&rules, Some(&updates[4]));
+        assert_eq!(
+            dot,
+            "digraph rules {
+    61;
+    13;
+    29;
+    61 -> 13;
+    29 -> 13 [color=red];
+    61 -> 29;
+}
+"
+        );
+        // all ten pairs of the five pages are ordered, one of them the wrong way
+        let dot = to_dot(&rules, Some(&updates[3]));
+        assert_eq!(dot.matches(" -> ").count(), 10);
+        assert_eq!(dot.matches("[color=red]").count(), 1);
+        assert!(dot.contains("    97 -> 75 [color=red];\n"));
+    }
+}
@@ -1,10 +1,87 @@
-enum LineValidity {
-    Valid,
-    Invalid((usize, usize)), // index of invalid number, index where it should go to be valid
-}
+use std::fmt;
+
+use rules::RuleSet;
+
+mod analysis;
+mod dot;
+mod rules;
+mod validation;
 
 fn main() {
     let s = std::fs::read_to_string("input").unwrap();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // `--dot [--update N]` prints the rules as a Graphviz graph instead of the results, with
+    // `--update` only those between the pages of update N and its broken rules in red
+    if args.iter().any(|a| a == "--update") && !args.iter().any(|a| a == "--dot") {
+        eprintln!("--update only works together with --dot");
+        std::process::exit(1);
+    }
+    if args.iter().any(|a| a == "--dot") {
+        let (rules, lines) = parse_input(&s);
+        let update = args.iter().position(|a| a == "--update").map(|pos| {
+            let index = args.get(pos + 1).and_then(|n| n.parse::<usize>().ok());
+            match index.and_then(|index| lines.get(index)) {
+                Some(line) => line.as_slice(),
+                None => {
+                    eprintln!(
+                        "--update needs an update number from 0 to {}",
+                        lines.len().saturating_sub(1)
+                    );
+                    std::process::exit(1);
+                }
+            }
+        });
+        print!("{}", dot::to_dot(&rules, update));
+        return;
+    }
+
+    // The reports below replace the results, so they also work for rules that form a cycle in
+    // some update, which part 2 cannot correct.
+
+    // `--check`: duplicate, contradictory and cyclic rules, and unordered pages
+    if args.iter().any(|a| a == "--check") {
+        let (rules, lines) = parse_input(&s);
+        let problems = analysis::check(&rules, &lines);
+        for problem in &problems {
+            println!("{}", problem.description());
+        }
+        println!("{} problems", problems.len());
+        return;
+    }
+
+    // `--violations` / `--violations-json`: every broken rule and the fewest moves to
+    // fix each update
+    if args.iter().any(|a| a == "--violations") {
+        let (rules, lines) = parse_input(&s);
+        for report in validation::validate(&RuleSet::new(&rules), &lines) {
+            println!("{}", report.text());
+        }
+        return;
+    } else if args.iter().any(|a| a == "--violations-json") {
+        let (rules, lines) = parse_input(&s);
+        let reports = validation::validate(&RuleSet::new(&rules), &lines);
+        let rows: Vec<String> = reports.iter().map(|r| r.to_json()).collect();
+        println!("[\n  {}\n]", rows.join(",\n  "));
+        return;
+    }
+
+    // `--explain` / `--explain-json`: list which updates were reordered and how
+    if args.iter().any(|a| a == "--explain") {
+        println!(
+            "{:>6}  {:<5}  {:>6}  update -> corrected (moves)",
+            "update", "valid", "middle"
+        );
+        for expl in explain(&s) {
+            println!("{}", expl.table_row());
+        }
+        return;
+    } else if args.iter().any(|a| a == "--explain-json") {
+        let rows: Vec<String> = explain(&s).iter().map(|e| e.to_json()).collect();
+        println!("[\n  {}\n]", rows.join(",\n  "));
+        return;
+    }
+
     let valid_p1 = part1(&s, false);
     let res1 = center_sum(valid_p1);
     println!("Part 1: {}", res1);
@@ -13,6 +90,130 @@ fn main() {
     println!("Part 2: {}", res2);
 }
 
+/// A single step of the correction in part 2: `page` moved from index `from` to index `to`.
+#[derive(Debug, PartialEq)]
+struct Move {
+    page: u64,
+    from: usize,
+    to: usize,
+}
+
+impl Move {
+    fn to_json(&self) -> String {
+        json_object(&[
+            ("page", self.page.to_string()),
+            ("from", self.from.to_string()),
+            ("to", self.to.to_string()),
+        ])
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}->{}", self.page, self.from, self.to)
+    }
+}
+
+/// What happened to one update: valid as is, or corrected with the listed moves. An update
+/// whose rules form a cycle cannot be corrected and is left as it is.
+#[derive(Debug, PartialEq)]
+struct UpdateExplanation {
+    index: usize,
+    original: Vec<u64>,
+    valid: bool,
+    cyclic: bool,
+    corrected: Vec<u64>,
+    moves: Vec<Move>,
+}
+
+impl UpdateExplanation {
+    /// Middle page that goes into the sum of part 1 (valid) or part 2 (corrected), `None` if
+    /// the update could not be corrected.
+    fn middle(&self) -> Option<u64> {
+        (!self.cyclic).then(|| self.corrected[self.corrected.len().div_ceil(2) - 1])
+    }
+
+    fn table_row(&self) -> String {
+        let middle = self.middle().map_or("-".to_string(), |m| m.to_string());
+        let mut row = format!(
+            "{:>6}  {:<5}  {:>6}  {}",
+            self.index,
+            self.valid,
+            middle,
+            join(&self.original, ",")
+        );
+        if self.cyclic {
+            row.push_str(" -> rules form a cycle, see --check");
+        } else if !self.valid {
+            row.push_str(&format!(
+                " -> {} ({})",
+                join(&self.corrected, ","),
+                join(&self.moves, ", ")
+            ));
+        }
+        row
+    }
+
+    fn to_json(&self) -> String {
+        let moves = self.moves.iter().map(Move::to_json);
+        json_object(&[
+            ("index", self.index.to_string()),
+            ("original", json_array(&self.original)),
+            ("valid", self.valid.to_string()),
+            ("cyclic", self.cyclic.to_string()),
+            ("corrected", json_array(&self.corrected)),
+            ("moves", json_array(moves)),
+            (
+                "middle",
+                self.middle().map_or("null".to_string(), |m| m.to_string()),
+            ),
+        ])
+    }
+}
+
+/// A JSON object from field names and values that are already JSON.
+fn json_object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| format!("\"{}\": {}", name, value))
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// A JSON array of values that are already JSON, such as numbers.
+fn json_array(items: impl IntoIterator<Item = impl fmt::Display>) -> String {
+    format!("[{}]", join(items, ", "))
+}
+
+/// The items written one after the other with `sep` in between.
+fn join(items: impl IntoIterator<Item = impl fmt::Display>, sep: &str) -> String {
+    let items: Vec<String> = items.into_iter().map(|item| item.to_string()).collect();
+    items.join(sep)
+}
+
+/// Explain for every update whether it was valid, and how it was corrected if not.
+fn explain(s: &str) -> Vec<UpdateExplanation> {
+    let (rules, lines) = parse_input(s);
+    let rules = RuleSet::new(&rules);
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let fix = validation::fewest_moves(&rules, &line);
+            let cyclic = fix.is_none();
+            let (corrected, moves) = fix.unwrap_or_else(|| (line.clone(), vec![]));
+            UpdateExplanation {
+                index,
+                valid: rules.is_ordered(&line),
+                cyclic,
+                corrected,
+                original: line,
+                moves,
+            }
+        })
+        .collect()
+}
+
 fn center_sum(valid_lines: Vec<Vec<u64>>) -> u64 {
     let mut center_sum = 0;
     for line in valid_lines {
@@ -46,39 +247,18 @@ fn parse_input(s: &str) -> (Vec<(u64, u64)>, Vec<Vec<u64>>) {
     (rules, lists)
 }
 
-fn check_line_validity(line: &[u64], rules: &Vec<(u64, u64)>) -> LineValidity {
-    for it in 1..line.len() {
-        let left = line[it - 1];
-        let right = line[it];
-        for rule in rules {
-            if rule.1 == left && line[it..].iter().any(|&x| x == rule.0) {
-                // find the index of the invalid number
-                let ind_inv = line[it..].iter().position(|&x| x == rule.0).unwrap()+it;
-                return LineValidity::Invalid((ind_inv, it - 1));
-            } else if rule.0 == right && line[..it].iter().any(|&x| x == rule.1) {
-                let ind_inv = line[..it].iter().position(|&x| x == rule.1).unwrap()+it;
-                return LineValidity::Invalid((ind_inv, it));
-            }
-        }
-    }
-    LineValidity::Valid
-}
-
 /// Part 1, returns the valid lines.
 fn part1(s: &str, get_invalid_lines: bool) -> Vec<Vec<u64>> {
     let (rules, lines) = parse_input(s);
+    let rules = RuleSet::new(&rules);
     let mut valid_lines = vec![];
     let mut invalid_lines = vec![];
     for line in &lines {
-        match check_line_validity(line, &rules) {
-            LineValidity::Valid => {
-                valid_lines.push(line.to_vec());
-            }
-            _ => {
-                invalid_lines.push(line.to_vec());
-            }
+        if rules.is_ordered(line) {
+            valid_lines.push(line.to_vec());
+        } else {
+            invalid_lines.push(line.to_vec());
         }
-
     }
     if !get_invalid_lines {
         valid_lines
@@ -89,23 +269,15 @@ fn part1(s: &str, get_invalid_lines: bool) -> Vec<Vec<u64>> {
 
 fn part2(s: &str) -> u64 {
     let (rules, _) = parse_input(s);
+    let rules = RuleSet::new(&rules);
     let mut good_lines = vec![];
     let invalid_lines = part1(s, true);
 
-    for mut line in invalid_lines {
-        loop {
-            match check_line_validity(&line, &rules) {
-                LineValidity::Valid => {
-                    good_lines.push(line);
-                    break;
-                }
-                LineValidity::Invalid((ind_inv, ind)) => {
-                    let tmp = line.remove(ind_inv);
-                    line.insert(ind, tmp);
-                }
-            }
-
-        }
+    for line in invalid_lines {
+        let line = rules
+            .sorted(&line)
+            .expect("the rules between the pages of an update form a cycle, see --check");
+        good_lines.push(line);
     }
 
     center_sum(good_lines)
@@ -115,7 +287,53 @@ fn part2(s: &str) -> u64 {
 mod tests {
     use super::*;
 
-    const TDAT: &str = "47|53
+    // The original correction of part 2, kept to check the topological sort against.
+    enum LineValidity {
+        Valid,
+        Invalid((usize, usize)), // index of invalid number, index where it should go to be valid
+    }
+
+    fn check_line_validity(line: &[u64], rules: &Vec<(u64, u64)>) -> LineValidity {
+        for it in 1..line.len() {
+            let left = line[it - 1];
+            let right = line[it];
+            for rule in rules {
+                if rule.1 == left && line[it..].contains(&rule.0) {
+                    // find the index of the invalid number
+                    let ind_inv = line[it..].iter().position(|&x| x == rule.0).unwrap() + it;
+                    return LineValidity::Invalid((ind_inv, it - 1));
+                } else if rule.0 == right && line[..it].contains(&rule.1) {
+                    let ind_inv = line[..it].iter().position(|&x| x == rule.1).unwrap() + it;
+                    return LineValidity::Invalid((ind_inv, it));
+                }
+            }
+        }
+        LineValidity::Valid
+    }
+
+    /// Move invalid numbers one at a time until the line is valid, returns it and all moves made.
+    pub(crate) fn correct_line(
+        mut line: Vec<u64>,
+        rules: &Vec<(u64, u64)>,
+    ) -> (Vec<u64>, Vec<Move>) {
+        let mut moves = vec![];
+        loop {
+            match check_line_validity(&line, rules) {
+                LineValidity::Valid => return (line, moves),
+                LineValidity::Invalid((ind_inv, ind)) => {
+                    let tmp = line.remove(ind_inv);
+                    line.insert(ind, tmp);
+                    moves.push(Move {
+                        page: tmp,
+                        from: ind_inv,
+                        to: ind,
+                    });
+                }
+            }
+        }
+    }
+
+    pub(crate) const TDAT: &str = "47|53
 97|13
 97|61
 97|47
@@ -161,4 +379,49 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TDAT), 123);
     }
+
+    #[test]
+    fn test_explain_cycle() {
+        let expl = explain("1|2\n2|3\n3|1\n\n1,2,3\n2,1");
+        assert!(expl[0].cyclic && !expl[0].valid);
+        assert_eq!(expl[0].corrected, vec![1, 2, 3]);
+        assert_eq!(expl[0].middle(), None);
+        assert_eq!(
+            expl[0].table_row(),
+            "     0  false       -  1,2,3 -> rules form a cycle, see --check"
+        );
+        assert!(expl[0]
+            .to_json()
+            .ends_with("\"moves\": [], \"middle\": null}"));
+        assert!(!expl[1].cyclic);
+        assert_eq!(expl[1].corrected, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_explain() {
+        let expl = explain(TDAT);
+        assert_eq!(expl.len(), 6);
+        assert!(expl[0].valid);
+        assert_eq!(expl[0].middle(), Some(61));
+        assert!(!expl[3].valid);
+        assert_eq!(expl[3].corrected, vec![97, 75, 47, 61, 53]);
+        assert_eq!(
+            expl[3].moves,
+            vec![Move {
+                page: 97,
+                from: 1,
+                to: 0
+            }]
+        );
+        let sum_corrected: u64 = expl
+            .iter()
+            .filter(|e| !e.valid)
+            .filter_map(|e| e.middle())
+            .sum();
+        assert_eq!(sum_corrected, 123);
+        assert_eq!(
+            expl[3].table_row(),
+            "     3  false      47  75,97,47,61,53 -> 97,75,47,61,53 (97 1->0)"
+        );
+    }
 }
@@ -0,0 +1,112 @@
+//! Page ordering rules indexed by page, and ordering updates by them.
+//!
+//! Rules are kept as a map from each page to the pages that must come after it, so checking
+//! one pair is a lookup instead of a scan over all rules. An update is put in order by sorting
+//! the graph of the rules between its own pages topologically.
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+#[derive(Debug, Default)]
+pub struct RuleSet {
+    /// For every page, the pages that must come after it.
+    after: HashMap<u64, HashSet<u64>>,
+}
+
+impl RuleSet {
+    pub fn new(rules: &[(u64, u64)]) -> RuleSet {
+        let mut set = RuleSet::default();
+        for &(a, b) in rules {
+            set.after.entry(a).or_default().insert(b);
+        }
+        set
+    }
+
+    /// Whether a rule says `a` must come before `b`.
+    pub fn requires(&self, a: u64, b: u64) -> bool {
+        self.after.get(&a).is_some_and(|after| after.contains(&b))
+    }
+
+    /// Pages that must come after `a`, in no particular order.
+    pub fn after(&self, a: u64) -> impl Iterator<Item = u64> + '_ {
+        self.after.get(&a).into_iter().flatten().copied()
+    }
+
+    /// Whether no rule is broken by the order of `line`.
+    pub fn is_ordered(&self, line: &[u64]) -> bool {
+        (0..line.len()).all(|it| line[it + 1..].iter().all(|&b| !self.requires(b, line[it])))
+    }
+
+    /// The pages of `line` in an order that follows the rules, or `None` if the rules between
+    /// them contain a cycle. Pages not ordered by any rule keep their relative order, so an
+    /// update that is already valid is returned unchanged.
+    pub fn sorted(&self, line: &[u64]) -> Option<Vec<u64>> {
+        // edges between positions of the update, and the number of edges into each position
+        let mut succ: Vec<Vec<usize>> = vec![vec![]; line.len()];
+        let mut indegree = vec![0; line.len()];
+        for (it, &a) in line.iter().enumerate() {
+            for (jt, &b) in line.iter().enumerate() {
+                if it != jt && self.requires(a, b) {
+                    succ[it].push(jt);
+                    indegree[jt] += 1;
+                }
+            }
+        }
+
+        // Kahn's algorithm, always taking the earliest free position
+        let mut free: BinaryHeap<Reverse<usize>> = (0..line.len())
+            .filter(|&it| indegree[it] == 0)
+            .map(Reverse)
+            .collect();
+        let mut order = Vec::with_capacity(line.len());
+        while let Some(Reverse(it)) = free.pop() {
+            order.push(line[it]);
+            for &jt in &succ[it] {
+                indegree[jt] -= 1;
+                if indegree[jt] == 0 {
+                    free.push(Reverse(jt));
+                }
+            }
+        }
+        (order.len() == line.len()).then_some(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+    use crate::tests::{correct_line, TDAT};
+
+    #[test]
+    fn test_sorted() {
+        let (rules, lines) = parse_input(TDAT);
+        let set = RuleSet::new(&rules);
+        assert_eq!(set.sorted(&lines[0]).unwrap(), lines[0]);
+        assert_eq!(set.sorted(&lines[3]).unwrap(), vec![97, 75, 47, 61, 53]);
+        assert_eq!(set.sorted(&lines[4]).unwrap(), vec![61, 29, 13]);
+        assert_eq!(set.sorted(&lines[5]).unwrap(), vec![97, 75, 47, 29, 13]);
+        let valid: Vec<bool> = lines.iter().map(|l| set.is_ordered(l)).collect();
+        assert_eq!(valid, vec![true, true, true, false, false, false]);
+    }
+
+    #[test]
+    fn test_unconstrained_and_cyclic() {
+        let set = RuleSet::new(&[(1, 2), (2, 3), (3, 1), (5, 4)]);
+        assert_eq!(set.sorted(&[9, 4, 8, 5, 7]).unwrap(), vec![9, 8, 5, 4, 7]);
+        assert_eq!(set.sorted(&[]).unwrap(), vec![]);
+        assert_eq!(set.sorted(&[3, 1]).unwrap(), vec![3, 1]);
+        assert!(set.sorted(&[4, 3, 2, 1]).is_none());
+    }
+
+    #[test]
+    fn test_matches_correct_line() {
+        let s = std::fs::read_to_string("input").unwrap();
+        let (rules, lines) = parse_input(&s);
+        let set = RuleSet::new(&rules);
+        for line in lines {
+            let (corrected, moves) = correct_line(line.clone(), &rules);
+            assert_eq!(set.is_ordered(&line), moves.is_empty());
+            assert_eq!(set.sorted(&line).unwrap(), corrected);
+        }
+    }
+}
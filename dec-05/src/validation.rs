@@ -0,0 +1,427 @@
+//! Reports of the rules each update breaks, and the fewest moves that put it in order.
+//!
+//! A page that is not moved keeps its place relative to the other pages that are not moved. So
+//! the pages left in place must not include two that are in the wrong order, taking into account
+//! rules that order them through other pages of the update. Being in the wrong order that way is
+//! itself a partial order on the pages, and the largest set of pages of which no two are in the
+//! wrong order is its largest antichain. That is found from a maximum bipartite matching, by
+//! Dilworth's and König's theorems. Every other page is then moved once.
+use crate::rules::RuleSet;
+use crate::{join, json_array, json_object, Move};
+
+/// A broken rule `x|y`: page `x` must come before page `y`, but is found after it.
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    pub x: u64,
+    pub y: u64,
+    pub x_pos: usize,
+    pub y_pos: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UpdateReport {
+    pub index: usize,
+    pub update: Vec<u64>,
+    pub violations: Vec<Violation>,
+    /// Fewest moves that fix the update, applied one after the other.
+    pub moves: Vec<Move>,
+    /// The update after the moves, `None` if the rules between its pages form a cycle.
+    pub corrected: Option<Vec<u64>>,
+}
+
+impl UpdateReport {
+    pub fn text(&self) -> String {
+        let mut out = format!("update {}: {}", self.index, join(&self.update, ","));
+        if self.violations.is_empty() {
+            out.push_str(" is valid");
+            return out;
+        }
+        for v in &self.violations {
+            out.push_str(&format!(
+                "\n  rule {}|{} broken: {} at {}, {} at {}",
+                v.x, v.y, v.x, v.x_pos, v.y, v.y_pos
+            ));
+        }
+        match &self.corrected {
+            Some(corrected) => out.push_str(&format!(
+                "\n  fix: {} -> {}",
+                join(&self.moves, ", "),
+                join(corrected, ",")
+            )),
+            None => out.push_str("\n  cannot be fixed, the rules form a cycle"),
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let violations = self.violations.iter().map(|v| {
+            json_object(&[
+                ("rule", json_array([v.x, v.y])),
+                ("x_pos", v.x_pos.to_string()),
+                ("y_pos", v.y_pos.to_string()),
+            ])
+        });
+        let corrected = match &self.corrected {
+            Some(corrected) => json_array(corrected),
+            None => "null".into(),
+        };
+        json_object(&[
+            ("index", self.index.to_string()),
+            ("update", json_array(&self.update)),
+            ("violations", json_array(violations)),
+            ("moves", json_array(self.moves.iter().map(Move::to_json))),
+            ("corrected", corrected),
+        ])
+    }
+}
+
+/// Report for every update.
+pub fn validate(rules: &RuleSet, updates: &[Vec<u64>]) -> Vec<UpdateReport> {
+    updates
+        .iter()
+        .enumerate()
+        .map(|(index, update)| {
+            let fix = fewest_moves(rules, update);
+            let (corrected, moves) = match fix {
+                Some((corrected, moves)) => (Some(corrected), moves),
+                None => (None, vec![]),
+            };
+            UpdateReport {
+                index,
+                update: update.clone(),
+                violations: violations(rules, update),
+                moves,
+                corrected,
+            }
+        })
+        .collect()
+}
+
+/// Every pair of pages in `update` in the wrong order for some rule.
+pub fn violations(rules: &RuleSet, update: &[u64]) -> Vec<Violation> {
+    let mut found = vec![];
+    for (y_pos, &y) in update.iter().enumerate() {
+        for (x_pos, &x) in update.iter().enumerate().skip(y_pos + 1) {
+            if rules.requires(x, y) {
+                found.push(Violation { x, y, x_pos, y_pos });
+            }
+        }
+    }
+    found
+}
+
+/// The fewest moves that put `update` in an order that follows the rules, and the order they
+/// lead to, or `None` if the rules between its pages form a cycle.
+pub(crate) fn fewest_moves(rules: &RuleSet, update: &[u64]) -> Option<(Vec<u64>, Vec<Move>)> {
+    // before[i][j]: the page at i must come before the page at j, directly or through others
+    let n = update.len();
+    let mut before: Vec<Vec<bool>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| i != j && rules.requires(update[i], update[j]))
+                .collect()
+        })
+        .collect();
+    for k in 0..n {
+        let via = before[k].clone();
+        for row in before.iter_mut().filter(|row| row[k]) {
+            for (b, v) in row.iter_mut().zip(&via) {
+                *b |= v;
+            }
+        }
+    }
+    if (0..n).any(|i| before[i][i]) {
+        return None;
+    }
+
+    // follow the rules, and keep the pages that stay in their current order
+    let keep = largest_unmoved(&before);
+    let kept: Vec<usize> = (0..n).filter(|&i| keep[i]).collect();
+    for pair in kept.windows(2) {
+        before[pair[0]][pair[1]] = true;
+    }
+    let order = earliest_first(&before);
+
+    // positions in `update` of the pages, in their current order
+    let mut line: Vec<usize> = (0..n).collect();
+    let mut moves = vec![];
+    for (it, &pos) in order.iter().enumerate() {
+        if keep[pos] {
+            continue;
+        }
+        let from = line.iter().position(|&p| p == pos).unwrap();
+        line.remove(from);
+        let to = match it {
+            0 => 0,
+            _ => line.iter().position(|&p| p == order[it - 1]).unwrap() + 1,
+        };
+        line.insert(to, pos);
+        moves.push(Move {
+            page: update[pos],
+            from,
+            to,
+        });
+    }
+    Some((order.iter().map(|&pos| update[pos]).collect(), moves))
+}
+
+/// Largest set of positions of which no two are in the wrong order, given the transitive
+/// `before` relation. Of several such sets, this prefers pages early in the update.
+fn largest_unmoved(before: &[Vec<bool>]) -> Vec<bool> {
+    // an edge from every position to each earlier one it has to come before
+    let n = before.len();
+    let wrong: Vec<Vec<usize>> = (0..n)
+        .map(|j| (0..j).filter(|&i| before[j][i]).collect())
+        .collect();
+
+    let mut matched: Vec<Option<usize>> = vec![None; n];
+    for j in 0..n {
+        augment(j, &wrong, &mut vec![false; n], &mut matched);
+    }
+
+    // König: everything reachable from unmatched left vertices along alternating paths
+    let mut is_matched = vec![false; n];
+    for &j in matched.iter().flatten() {
+        is_matched[j] = true;
+    }
+    let mut left: Vec<bool> = is_matched.iter().map(|m| !m).collect();
+    let mut right = vec![false; n];
+    let mut stack: Vec<usize> = (0..n).filter(|&j| left[j]).collect();
+    while let Some(j) = stack.pop() {
+        for &i in &wrong[j] {
+            if !right[i] {
+                right[i] = true;
+                if let Some(k) = matched[i] {
+                    if !left[k] {
+                        left[k] = true;
+                        stack.push(k);
+                    }
+                }
+            }
+        }
+    }
+    (0..n).map(|x| left[x] && !right[x]).collect()
+}
+
+/// Kuhn's augmenting path step: try to match `j` to one of the positions in `wrong[j]`.
+fn augment(
+    j: usize,
+    wrong: &[Vec<usize>],
+    seen: &mut [bool],
+    matched: &mut [Option<usize>],
+) -> bool {
+    for &i in &wrong[j] {
+        if seen[i] {
+            continue;
+        }
+        seen[i] = true;
+        if matched[i].is_none_or(|k| augment(k, wrong, seen, matched)) {
+            matched[i] = Some(j);
+            return true;
+        }
+    }
+    false
+}
+
+/// Positions in an order that follows the acyclic `before` relation, taking the earliest
+/// position that is free at each step.
+fn earliest_first(before: &[Vec<bool>]) -> Vec<usize> {
+    let n = before.len();
+    let mut indegree: Vec<usize> = (0..n)
+        .map(|j| (0..n).filter(|&i| before[i][j]).count())
+        .collect();
+    let mut order = Vec::with_capacity(n);
+    let mut done = vec![false; n];
+    while let Some(pos) = (0..n).find(|&p| !done[p] && indegree[p] == 0) {
+        done[pos] = true;
+        order.push(pos);
+        for j in 0..n {
+            if before[pos][j] {
+                indegree[j] -= 1;
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, tests::TDAT};
+
+    #[test]
+    fn test_violations() {
+        let (rules, updates) = parse_input(TDAT);
+        let reports = validate(&RuleSet::new(&rules), &updates);
+        assert!(reports[0].violations.is_empty() && reports[0].moves.is_empty());
+        assert_eq!(
+            reports[3].violations,
+            vec![Violation {
+                x: 97,
+                y: 75,
+                x_pos: 1,
+                y_pos: 0
+            }]
+        );
+        assert_eq!(
+            reports[3].moves,
+            vec![Move {
+                page: 97,
+                from: 1,
+                to: 0
+            }]
+        );
+        let broken: Vec<(u64, u64)> = reports[5].violations.iter().map(|v| (v.x, v.y)).collect();
+        assert_eq!(broken, vec![(75, 13), (29, 13), (47, 13), (47, 29)]);
+        assert_eq!(reports[5].moves.len(), 2);
+        assert_eq!(reports[5].corrected, Some(vec![97, 75, 47, 29, 13]));
+        assert_eq!(
+            reports[4].text(),
+            "update 4: 61,13,29\n  rule 29|13 broken: 29 at 2, 13 at 1\n  fix: 29 2->1 -> 61,29,13"
+        );
+    }
+
+    #[test]
+    fn test_fewest_moves() {
+        let chain = RuleSet::new(&[(1, 2), (2, 3), (3, 4), (4, 5)]);
+        // moving the 4 to the end is enough
+        let (corrected, moves) = fewest_moves(&chain, &[4, 1, 2, 3]).unwrap();
+        assert_eq!(corrected, vec![1, 2, 3, 4]);
+        assert_eq!(
+            moves,
+            vec![Move {
+                page: 4,
+                from: 0,
+                to: 3
+            }]
+        );
+        let (_, moves) = fewest_moves(&chain, &[5, 4, 3, 2, 1]).unwrap();
+        assert_eq!(moves.len(), 4);
+        assert_eq!(fewest_moves(&chain, &[]), Some((vec![], vec![])));
+    }
+
+    #[test]
+    fn test_fewest_moves_not_sorted_order() {
+        // the sorted order 1,2,3,4 is two moves away, moving the 2 to the front is one
+        let rules = RuleSet::new(&[(2, 3), (3, 4)]);
+        assert_eq!(rules.sorted(&[3, 4, 1, 2]).unwrap(), vec![1, 2, 3, 4]);
+        let (corrected, moves) = fewest_moves(&rules, &[3, 4, 1, 2]).unwrap();
+        assert_eq!(corrected, vec![2, 3, 4, 1]);
+        assert_eq!(
+            moves,
+            vec![Move {
+                page: 2,
+                from: 3,
+                to: 0
+            }]
+        );
+    }
+
+    /// Fewest moves by trying every order: a page left in place keeps its order with the other
+    /// pages left in place, so the most that can stay is the longest common subsequence.
+    fn brute_force(rules: &RuleSet, update: &[u64]) -> Option<usize> {
+        fn permutations(rest: &mut Vec<u64>, prefix: &mut Vec<u64>, out: &mut Vec<Vec<u64>>) {
+            if rest.is_empty() {
+                out.push(prefix.clone());
+            }
+            for it in 0..rest.len() {
+                let page = rest.remove(it);
+                prefix.push(page);
+                permutations(rest, prefix, out);
+                prefix.pop();
+                rest.insert(it, page);
+            }
+        }
+        let mut all = vec![];
+        permutations(&mut update.to_vec(), &mut vec![], &mut all);
+        let n = update.len();
+        all.iter()
+            .filter(|order| rules.is_ordered(order))
+            .map(|order| {
+                let mut lcs = vec![vec![0; n + 1]; n + 1];
+                for i in 0..n {
+                    for j in 0..n {
+                        lcs[i + 1][j + 1] = if update[i] == order[j] {
+                            lcs[i][j] + 1
+                        } else {
+                            lcs[i][j + 1].max(lcs[i + 1][j])
+                        };
+                    }
+                }
+                n - lcs[n][n]
+            })
+            .min()
+    }
+
+    #[test]
+    fn test_fewest_moves_brute_force() {
+        // small pseudo random rule sets and updates
+        let mut state: u64 = 12345;
+        let mut next = |below: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % below
+        };
+        for _ in 0..300 {
+            let n = 2 + next(5);
+            let mut rules = vec![];
+            for a in 1..=n {
+                for b in 1..=n {
+                    if a != b && next(10) < 3 {
+                        rules.push((a, b));
+                    }
+                }
+            }
+            let rules = RuleSet::new(&rules);
+            let mut update: Vec<u64> = (1..=n).collect();
+            for it in (1..update.len()).rev() {
+                update.swap(it, next(it as u64 + 1) as usize);
+            }
+
+            let fix = fewest_moves(&rules, &update);
+            assert_eq!(
+                fix.as_ref().map(|(_, moves)| moves.len()),
+                brute_force(&rules, &update),
+                "{:?}",
+                update
+            );
+            if let Some((corrected, moves)) = fix {
+                let mut line = update.clone();
+                for m in &moves {
+                    assert_eq!(line.remove(m.from), m.page);
+                    line.insert(m.to, m.page);
+                }
+                assert_eq!(line, corrected);
+                assert!(rules.is_ordered(&corrected));
+            }
+        }
+    }
+
+    #[test]
+    fn test_moves_fix_input() {
+        let s = std::fs::read_to_string("input").unwrap();
+        let (rules, updates) = parse_input(&s);
+        let rules = RuleSet::new(&rules);
+        for report in validate(&rules, &updates) {
+            let mut line = report.update.clone();
+            for m in &report.moves {
+                assert_eq!(line.remove(m.from), m.page);
+                line.insert(m.to, m.page);
+            }
+            assert_eq!(Some(line), report.corrected);
+            assert_eq!(report.violations.is_empty(), report.moves.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_json_and_cycle() {
+        let rules = RuleSet::new(&[(1, 2), (2, 1)]);
+        let reports = validate(&rules, &[vec![2, 1]]);
+        assert_eq!(reports[0].corrected, None);
+        assert_eq!(
+            reports[0].to_json(),
+            "{\"index\": 0, \"update\": [2, 1], \"violations\": [{\"rule\": [1, 2], \"x_pos\": 1, \"y_pos\": 0}], \"moves\": [], \"corrected\": null}"
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::Path;
+
 #[derive(Debug, Clone, PartialEq)]
 enum Direction {
     Up,
@@ -86,6 +88,22 @@ impl WalkingPath {
         }
     }
 
+    /// Render the pattern with all covered fields marked by `X`, like in the puzzle.
+    fn render_covered(&self) -> String {
+        let mut out = String::new();
+        for (row, line) in self.pattern.iter().enumerate() {
+            for (col, ch) in line.iter().enumerate() {
+                if self.covered[row][col] > 0 {
+                    out.push('X');
+                } else {
+                    out.push(*ch);
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+
     fn sum_covered(&self) -> usize {
         self.covered.iter().flatten().sum()
     }
@@ -110,17 +128,87 @@ impl WalkingPath {
     }
 
     fn count_loops(&mut self) -> usize {
-        let mut loops = 0;
-        for nrow in 0..self.pattern.len() {
+        self.count_loops_from(0, 0, |_, _| ())
+    }
+
+    /// Count loops starting at row `start_row` with `loops` already found in the rows before.
+    /// After each row, `row_done` is called with the next row to check and the loops so far.
+    fn count_loops_from(&mut self, start_row: usize, mut loops: usize, mut row_done: impl FnMut(usize, usize)) -> usize {
+        for nrow in start_row..self.pattern.len() {
             for ncol in 0..self.pattern[0].len() {
                 if self.is_loop(nrow, ncol) {
                     loops += 1;
                 }
             }
+            row_done(nrow + 1, loops);
         }
         loops
     }
 
+    /// FNV-1a hash of the pattern, saved with the cursor so it is not applied to another input.
+    fn pattern_hash(&self) -> u64 {
+        self.original_pattern
+            .iter()
+            .flat_map(|row| row.iter().chain(&['\n']))
+            .flat_map(|&ch| u32::from(ch).to_le_bytes())
+            .fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+    }
+
+    /// Count loops like `count_loops`, but save the search cursor to `path` after every row,
+    /// through a temporary file so an interruption never leaves half a cursor.
+    /// If `path` already holds a cursor for this pattern, the search resumes from there; a cursor
+    /// for another pattern or one that cannot be read is an error.
+    fn count_loops_with_checkpoint(&mut self, path: &Path) -> std::io::Result<usize> {
+        let (start_row, loops) = match std::fs::read_to_string(path) {
+            Ok(content) => self.parse_cursor(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (0, 0),
+            Err(e) => return Err(e),
+        };
+
+        let (rows, cols) = (self.pattern.len(), self.pattern[0].len());
+        let hash = self.pattern_hash();
+        let mut saved = Ok(());
+        let loops = self.count_loops_from(start_row, loops, |next_row, loops| {
+            if saved.is_ok() {
+                let content = format!(
+                    "pattern {:016x}\nrows {}\ncols {}\nnext_row {}\nloops {}\n",
+                    hash, rows, cols, next_row, loops
+                );
+                let tmp = path.with_extension("tmp");
+                saved = std::fs::write(&tmp, content).and_then(|_| std::fs::rename(&tmp, path));
+            }
+        });
+        saved.map(|_| loops)
+    }
+
+    /// Read a cursor saved by `count_loops_with_checkpoint`, returns the next row and loops so far.
+    fn parse_cursor(&self, content: &str) -> std::io::Result<(usize, usize)> {
+        let invalid = |msg| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+        let mut hash = None;
+        let mut values = [None; 4];
+        for line in content.lines() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            if key == "pattern" {
+                hash = u64::from_str_radix(value, 16).ok();
+                continue;
+            }
+            let ind = ["rows", "cols", "next_row", "loops"].iter().position(|k| *k == key);
+            if let (Some(ind), Ok(value)) = (ind, value.parse::<usize>()) {
+                values[ind] = Some(value);
+            }
+        }
+        let (Some(hash), [Some(rows), Some(cols), Some(next_row), Some(loops)]) = (hash, values) else {
+            return Err(invalid("checkpoint is incomplete"));
+        };
+        if hash != self.pattern_hash() || rows != self.pattern.len() || cols != self.pattern[0].len() {
+            return Err(invalid("checkpoint does not belong to this pattern"));
+        }
+        if next_row > rows {
+            return Err(invalid("checkpoint is past the last row"));
+        }
+        Ok((next_row, loops))
+    }
+
     fn reset(&mut self) {
         self.covered = vec![vec![0; self.pattern[0].len()]; self.pattern.len()];
         self.covered_directions = vec![vec![vec![]; self.pattern[0].len()]; self.pattern.len()];
@@ -141,7 +229,25 @@ fn main() {
     let mut path = WalkingPath::new(&input);
     path.walk();
     println!("Result Part 1: {}", path.sum_covered());
-    println!("Result Part 2: {}", path.count_loops());
+    let walked = path.render_covered();
+
+    // Optional `--checkpoint FILE`: save the loop search after every row and resume from there
+    // Optional `--grid`: print the walked path of part 1 at the end
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let checkpoint = args.windows(2).find(|pair| pair[0] == "--checkpoint").map(|pair| &pair[1]);
+    match checkpoint {
+        Some(file) => match path.count_loops_with_checkpoint(Path::new(file)) {
+            Ok(loops) => println!("Result Part 2: {}", loops),
+            Err(e) => {
+                eprintln!("checkpoint {}: {}", file, e);
+                std::process::exit(1);
+            }
+        },
+        None => println!("Result Part 2: {}", path.count_loops()),
+    }
+    if args.iter().any(|arg| arg == "--grid") {
+        print!("{}", walked);
+    }
 }
 
 #[cfg(test)]
@@ -173,10 +279,61 @@ mod tests {
         assert_eq!(path.sum_covered(), 41);
     }
 
+    #[test]
+    fn test_render_covered() {
+        let mut path = WalkingPath::new(PAT);
+        path.walk();
+        let rendered = path.render_covered();
+        assert_eq!(rendered.matches('X').count(), 41);
+        assert_eq!(rendered.lines().next(), Some("....#....."));
+        assert_eq!(rendered.lines().nth(6), Some(".#XXXXXXX."));
+    }
+
     #[test]
     fn test_part_two() {
         let mut path = WalkingPath::new(PAT);
         assert_eq!(path.count_loops(), 6);
     }
-    
+
+    #[test]
+    fn test_part_two_checkpoint_resume() {
+        let file = std::env::temp_dir().join(format!("dec-06-cursor-{}.txt", std::process::id()));
+        // pretend a previous run got through the first 7 rows, which hold 1 of the loops
+        let mut path = WalkingPath::new(PAT);
+        let cursor = format!("pattern {:016x}\nrows 10\ncols 10\nnext_row 7\nloops 1\n", path.pattern_hash());
+        std::fs::write(&file, cursor).unwrap();
+        assert_eq!(path.count_loops_with_checkpoint(&file).unwrap(), 6);
+        let cursor = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        assert!(cursor.contains("next_row 10\nloops 6"));
+    }
+
+    #[test]
+    fn test_checkpoint_for_other_pattern() {
+        let path = WalkingPath::new(PAT);
+        let hash = path.pattern_hash();
+        assert!(path.parse_cursor("rows 130\ncols 130\nnext_row 6\nloops 3\n").is_err());
+        let cursor = format!("pattern {:016x}\nrows 10\ncols 10\nnext_row 6\nloops 3\n", hash);
+        assert_eq!(path.parse_cursor(&cursor).unwrap(), (6, 3));
+        // same size, one obstacle moved
+        let other = WalkingPath::new(&PAT.replacen("....#.....", "...#......", 1));
+        assert_ne!(other.pattern_hash(), hash);
+        assert!(other.parse_cursor(&cursor).is_err());
+        // no cursor without the hash
+        assert!(path.parse_cursor("rows 10\ncols 10\nnext_row 6\nloops 3\n").is_err());
+    }
+
+    #[test]
+    fn test_broken_checkpoint_is_reported() {
+        let file = std::env::temp_dir().join(format!("dec-06-broken-{}.txt", std::process::id()));
+        std::fs::write(&file, "garbage").unwrap();
+        let mut path = WalkingPath::new(PAT);
+        let result = path.count_loops_with_checkpoint(&file);
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        assert!(result.is_err());
+        // and left alone
+        assert_eq!(content, "garbage");
+    }
+
 }
@@ -2,62 +2,184 @@ fn main() {
     let data = std::fs::read_to_string("input").unwrap();
     println!("Part 1: {}", part1(&data));
     println!("Part 2: {}", part2(&data));
+
+    // Optional `--explain` / `--explain-json`: list which equations matched with which operators
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--explain") {
+        println!(
+            "{:>4}  {:<12}  {:<12}  equation",
+            "line", "ops part 1", "ops part 2"
+        );
+        for expl in explain(&data) {
+            println!("{}", expl.table_row());
+        }
+    } else if args.iter().any(|a| a == "--explain-json") {
+        let rows: Vec<String> = explain(&data).iter().map(|e| e.to_json()).collect();
+        println!("[\n  {}\n]", rows.join(",\n  "));
+    }
 }
 
 /// Check if any combinations of + and * for input numbers can result in result.
 fn check_line_p1(result: u64, input: &[u64]) -> bool {
+    find_ops_p1(result, input).is_some()
+}
+
+/// First combination of + and * (as operator string, e.g. `+*`) that results in result.
+fn find_ops_p1(result: u64, input: &[u64]) -> Option<String> {
     let len_ops = input.len() - 1;
 
     let total_combinations = 2u64.pow(len_ops as u32);
 
     for ops_bin in 0..total_combinations {
         let mut result_test = input[0];
+        let mut ops = String::new();
         for ot in (0..len_ops).rev() {
             match ops_bin >> ot & 1 {
                 0 => {
                     result_test += input[len_ops - ot];
+                    ops.push('+');
                 }
                 _ => {
                     result_test *= input[len_ops - ot];
+                    ops.push('*');
                 }
             }
         }
         if result_test == result {
-            return true;
+            return Some(ops);
         }
     }
-    false
+    None
 }
 
 /// Check if any combinations of +, * and concat for input numbers can result in result.
 fn check_line_p2(result: u64, input: &[u64]) -> bool {
+    find_ops_p2(result, input).is_some()
+}
+
+/// First combination of +, * and concat (`|`) that results in result, e.g. `*|*`.
+fn find_ops_p2(result: u64, input: &[u64]) -> Option<String> {
     let len_ops = input.len() - 1;
 
     let total_combinations = 3u64.pow(len_ops as u32);
 
     for ops_bin in 0..total_combinations {
         let mut result_test = input[0];
+        let mut ops = String::new();
         for ot in (0..len_ops).rev() {
             match ops_bin / 3u64.pow(ot as u32) % 3 {
                 0 => {
                     result_test += input[len_ops - ot];
+                    ops.push('+');
                 }
                 1 => {
                     result_test *= input[len_ops - ot];
+                    ops.push('*');
                 }
                 _ => {
                     let rhs = input[len_ops - ot];
                     let multiplier = rhs.ilog10() + 1;
                     result_test *= 10u64.pow(multiplier);
                     result_test += rhs;
+                    ops.push('|');
                 }
             }
         }
         if result_test == result {
-            return true;
+            return Some(ops);
+        }
+    }
+    None
+}
+
+/// Which operators (if any) made an equation true in part 1 and part 2.
+#[derive(Debug, PartialEq)]
+struct EquationExplanation {
+    /// Line of the equation in the input, counted from 1.
+    line: usize,
+    result: u64,
+    input: Vec<u64>,
+    ops_p1: Option<String>,
+    ops_p2: Option<String>,
+}
+
+impl EquationExplanation {
+    /// The equation written out with the given operators, `||` for concatenation.
+    fn expression(&self, ops: &str) -> String {
+        let mut expr = self.input[0].to_string();
+        for (op, num) in ops.chars().zip(&self.input[1..]) {
+            let op = if op == '|' {
+                "||".to_string()
+            } else {
+                op.to_string()
+            };
+            expr.push_str(&format!(" {} {}", op, num));
         }
+        expr
+    }
+
+    fn table_row(&self) -> String {
+        let ops = |ops: &Option<String>| ops.clone().unwrap_or("-".to_string());
+        let equation = match self.ops_p1.as_ref().or(self.ops_p2.as_ref()) {
+            Some(ops) => format!("{} = {}", self.result, self.expression(ops)),
+            None => {
+                let input: Vec<String> = self.input.iter().map(|x| x.to_string()).collect();
+                format!("{}: {} (no match)", self.result, input.join(" "))
+            }
+        };
+        format!(
+            "{:>4}  {:<12}  {:<12}  {}",
+            self.line,
+            ops(&self.ops_p1),
+            ops(&self.ops_p2),
+            equation
+        )
+    }
+
+    fn to_json(&self) -> String {
+        let ops = |ops: &Option<String>| match ops {
+            Some(ops) => format!("\"{}\"", ops),
+            None => "null".to_string(),
+        };
+        json_object(&[
+            ("line", self.line.to_string()),
+            ("result", self.result.to_string()),
+            ("input", json_array(&self.input)),
+            ("ops_part1", ops(&self.ops_p1)),
+            ("ops_part2", ops(&self.ops_p2)),
+        ])
     }
-    false
+}
+
+/// A JSON object from field names and values that are already JSON.
+fn json_object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| format!("\"{}\": {}", name, value))
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// A JSON array of numbers.
+fn json_array(items: impl IntoIterator<Item = impl std::fmt::Display>) -> String {
+    let items: Vec<String> = items.into_iter().map(|item| item.to_string()).collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn explain(data: &str) -> Vec<EquationExplanation> {
+    let (results, input) = parse_data(data);
+    results
+        .into_iter()
+        .zip(input)
+        .enumerate()
+        .map(|(index, (result, input))| EquationExplanation {
+            line: index + 1,
+            result,
+            ops_p1: find_ops_p1(result, &input),
+            ops_p2: find_ops_p2(result, &input),
+            input,
+        })
+        .collect()
 }
 
 fn parse_data(data: &str) -> (Vec<u64>, Vec<Vec<u64>>) {
@@ -142,4 +264,25 @@ mod tests {
     fn test_pat2_ex() {
         assert_eq!(part2(DATA), 11387);
     }
+
+    #[test]
+    fn test_explain() {
+        let expl = explain(DATA);
+        assert_eq!(expl[1].ops_p1, Some("+*".to_string()));
+        assert_eq!(expl[1].expression("+*"), "81 + 40 * 27");
+        assert_eq!(expl[2].ops_p2, None);
+        assert_eq!(expl[4].ops_p1, None);
+        assert_eq!(
+            expl[4].expression(expl[4].ops_p2.as_ref().unwrap()),
+            "6 * 8 || 6 * 15"
+        );
+        assert_eq!(
+            expl[3].to_json(),
+            "{\"line\": 4, \"result\": 156, \"input\": [15, 6], \"ops_part1\": null, \"ops_part2\": \"|\"}"
+        );
+        assert_eq!(
+            expl[3].table_row(),
+            "   4  -             |             156 = 15 || 6"
+        );
+    }
 }
@@ -9,6 +9,10 @@ fn main() {
 
     grid.calc_antipodes_new().unwrap();
     println!("Results part 2: {}", grid.antipodes.len());
+
+    if std::env::args().any(|arg| arg == "--grid") {
+        print!("{}", grid.render());
+    }
 }
 
 #[derive(Debug, Clone, Copy, Eq, Hash, PartialEq, PartialOrd)]
@@ -170,6 +174,26 @@ impl Grid {
         }
     }
 
+    /// Render the grid with antennas and antipodes (`#`) as in the puzzle. Antennas are drawn
+    /// on top of antipodes at the same place.
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for lt in 0..=self.max.row {
+            for ct in 0..=self.max.col {
+                // same row / col convention as in `Grid::new`
+                let pt = Point { col: lt, row: ct };
+                let antenna = self.antennas.iter().find(|(_, pts)| pts.contains(&pt));
+                match antenna {
+                    Some((ch, _)) => out.push(*ch),
+                    None if self.antipodes.contains(&pt) => out.push('#'),
+                    None => out.push('.'),
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+
     fn calc_antipodes(&mut self) -> Result<(), String> {
         for items in self.antennas.values() {
             for (it, p1) in items.iter().enumerate() {
@@ -247,8 +271,8 @@ mod tests {
         let p0 = Point { col: 1, row: 8 };
         let pa = Point { col: 9, row: 9 };
         // Make sure p0 is in vec_0
-        assert!(vec_0.iter().any(|x| *x == p0));
-        assert!(vec_a.iter().any(|x| *x == pa));
+        assert!(vec_0.contains(&p0));
+        assert!(vec_a.contains(&pa));
     }
 
     #[test]
@@ -262,10 +286,10 @@ mod tests {
         let res1 = p1.antipodes(p2, max);
         let res2 = p2.antipodes(p1, max);
 
-        assert!(res1.iter().any(|x| ap1_exp == *x));
-        assert!(res1.iter().any(|x| ap2_exp == *x));
-        assert!(res2.iter().any(|x| ap1_exp == *x));
-        assert!(res2.iter().any(|x| ap2_exp == *x));
+        assert!(res1.contains(&ap1_exp));
+        assert!(res1.contains(&ap2_exp));
+        assert!(res2.contains(&ap1_exp));
+        assert!(res2.contains(&ap2_exp));
     }
 
     #[test]
@@ -282,6 +306,24 @@ mod tests {
         assert_eq!(grid.antipodes.len(), 9);
     }
 
+    #[test]
+    fn test_render() {
+        let mut grid = Grid::new(DATA_T);
+        grid.calc_antipodes_new().unwrap();
+        let exp = "T....#....
+...T......
+.T....#...
+.........#
+..#.......
+..........
+...#......
+..........
+....#.....
+..........
+";
+        assert_eq!(grid.render(), exp);
+    }
+
     #[test]
     fn test_part2() {
         let mut grid = Grid::new(DATA);
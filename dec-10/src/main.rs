@@ -5,6 +5,10 @@ fn main() {
     let topo = TopoMap::new(&s);
     println!("Part 1: {}", topo.sum_trailhead_scores());
     println!("Part 2: {}", topo.sum_trailhead_ratings());
+
+    if std::env::args().any(|arg| arg == "--grid") {
+        print!("{}", topo.render());
+    }
 }
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
@@ -107,6 +111,30 @@ impl TopoMap {
         }
     }
 
+    /// Render the trails like in the puzzle: every field on a trail from a trailhead to a 9 shows
+    /// its altitude, all other fields show as `.`.
+    fn render(&self) -> String {
+        let mut on_trail = HashSet::new();
+        for trails in self.find_all_trails() {
+            for trail in trails {
+                on_trail.extend(trail.trail);
+            }
+        }
+
+        let mut out = String::new();
+        for (row, alts) in self.altitude.iter().enumerate() {
+            for (col, alt) in alts.iter().enumerate() {
+                if on_trail.contains(&Coord { row, col }) {
+                    out.push_str(&alt.to_string());
+                } else {
+                    out.push('.');
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+
     fn sum_trailhead_ratings(&self) -> usize {
         let trailheads = self.find_all_trails();
         let mut count = 0;
@@ -216,6 +244,28 @@ mod tests {
         assert_eq!(trails.len(), 2);
     }
 
+    #[test]
+    fn test_render() {
+        // the two trailheads at the bottom lead nowhere
+        let topo = TopoMap::new("0123\n7654\n8900");
+        assert_eq!(topo.render(), "0123\n7654\n89..\n");
+
+        let rendered = TopoMap::new(TOPO).render();
+        assert_eq!(rendered.matches('0').count(), 9);
+        assert_eq!(
+            rendered,
+            ".9010123
+.8121874
+87430965
+96549874
+45678903
+32019012
+01329801
+.04567..
+"
+        );
+    }
+
     #[test]
     fn test_p1() {
         let topo = TopoMap::new(TOPO);
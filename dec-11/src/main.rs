@@ -1,3 +1,7 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 fn main() {
     let data = std::fs::read_to_string("input").unwrap();
     let data: Vec<u64> = data.trim().split(" ").map(|x| x.parse().unwrap()).collect();
@@ -7,11 +11,135 @@ fn main() {
 
     println!("Part 1: {}", arr.get_number_stones());
 
+    // Part 2 takes way too long with this approach, so run it on a budget (10 s by default)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let budget = match Budget::from_args(&args) {
+        Ok(budget) => {
+            budget.unwrap_or_else(|| Budget::new().with_time_limit(Duration::from_secs(10)))
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Ctrl-C stops part 2 after the current blink and still prints the stones so far, a second
+    // Ctrl-C quits right away
+    let token = budget.clone();
+    ctrlc::set_handler(move || {
+        if token.is_cancelled() {
+            std::process::exit(130);
+        }
+        token.cancel();
+    })
+    .expect("cannot install the Ctrl-C handler");
+
     let mut arr = Arrangement::new(data);
-    //arr.blink_n(75);  // FIXME: takes way too long
-    println!("Part 2: {}", arr.get_number_stones());
+    match arr.blink_n_budgeted(75, &budget) {
+        None => println!("Part 2: {}", arr.get_number_stones()),
+        Some(reason) => println!(
+            "Part 2 stopped ({}) after {} blinks: {} stones so far",
+            reason,
+            arr.blinks,
+            arr.get_number_stones()
+        ),
+    }
+}
+
+/// Why a budgeted computation stopped before it was done.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum StopReason {
+    Cancelled,
+    StepLimit,
+    TimeLimit,
+}
+
+impl std::fmt::Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StopReason::Cancelled => write!(f, "cancelled"),
+            StopReason::StepLimit => write!(f, "step limit"),
+            StopReason::TimeLimit => write!(f, "time limit"),
+        }
+    }
+}
+
+/// Step and time limits plus a cancellation token, checked cooperatively by the solver.
+#[derive(Debug, Clone, Default)]
+struct Budget {
+    max_steps: Option<u64>,
+    deadline: Option<Instant>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Budget {
+    fn new() -> Budget {
+        Budget::default()
+    }
+
+    fn with_max_steps(mut self, steps: u64) -> Budget {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    /// Stop after `limit` from now, or earlier if the budget already has an earlier deadline.
+    fn with_time_limit(mut self, limit: Duration) -> Budget {
+        let deadline = Instant::now() + limit;
+        self.deadline = Some(self.deadline.map_or(deadline, |dl| dl.min(deadline)));
+        self
+    }
+
+    /// Build a budget from `--max-steps N` and / or `--max-seconds N`, `None` if neither is given.
+    fn from_args(args: &[String]) -> Result<Option<Budget>, String> {
+        let mut budget: Option<Budget> = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--max-steps" => {
+                    let steps = args
+                        .next()
+                        .and_then(|v| v.parse().ok())
+                        .ok_or("--max-steps needs a whole number of steps")?;
+                    budget = Some(budget.unwrap_or_default().with_max_steps(steps));
+                }
+                "--max-seconds" => {
+                    let limit = args
+                        .next()
+                        .and_then(|v| v.parse().ok())
+                        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                        .ok_or("--max-seconds needs a number of seconds, 0 or more")?;
+                    budget = Some(budget.unwrap_or_default().with_time_limit(limit));
+                }
+                _ => (),
+            }
+        }
+        Ok(budget)
+    }
+
+    /// Ask everyone sharing this budget to stop at their next check.
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Check if we may continue after `steps` steps, otherwise return why not.
+    fn check(&self, steps: u64) -> Option<StopReason> {
+        if self.is_cancelled() {
+            Some(StopReason::Cancelled)
+        } else if self.max_steps.is_some_and(|max| steps >= max) {
+            Some(StopReason::StepLimit)
+        } else if self.deadline.is_some_and(|dl| Instant::now() >= dl) {
+            Some(StopReason::TimeLimit)
+        } else {
+            None
+        }
+    }
 }
 
+#[allow(dead_code)]
 struct Stone2 {
     value: u64,
     sum_stones: u64,
@@ -36,11 +164,29 @@ impl Arrangement {
 
     // Blink multiple times 
     fn blink_n(&mut self, n: u64) {
-        for blk in 0..n {
+        for _ in 0..n {
             self.blink()
         }
     }
 
+    /// Blink up to `n` times, stopping early if the budget runs out. Every blink is a step.
+    ///
+    /// Returns `None` if all blinks were done, otherwise the reason for stopping. The
+    /// arrangement is then left at the last completed blink.
+    fn blink_n_budgeted(&mut self, n: u64, budget: &Budget) -> Option<StopReason> {
+        let mut steps = 0;
+        while steps < n {
+            if let Some(reason) = budget.check(steps) {
+                return Some(reason);
+            }
+            if let Some(reason) = self.blink_budgeted(budget, steps) {
+                return Some(reason);
+            }
+            steps += 1;
+        }
+        None
+    }
+
     /// Blink once
     fn blink(&mut self) {
         let mut new_row = Vec::new();
@@ -51,10 +197,27 @@ impl Arrangement {
         self.blinks += 1;
     }
 
+    /// Blink once, but abandon the blink if the budget runs out halfway through a long row.
+    fn blink_budgeted(&mut self, budget: &Budget, steps: u64) -> Option<StopReason> {
+        let mut new_row = Vec::new();
+        for (it, stone) in self.row.iter().enumerate() {
+            if it % (1 << 16) == 0 {
+                if let Some(reason) = budget.check(steps) {
+                    return Some(reason);
+                }
+            }
+            stone.split().iter().for_each(|x| new_row.push(*x));
+        }
+        self.row = new_row;
+        self.blinks += 1;
+        None
+    }
+
     fn get_number_stones(&self) -> usize {
         self.row.len()
     }
 
+    #[cfg(test)]
     fn get_values(&self) -> Vec<u64> {
         self.row.iter().map(|x| x.value).collect()
     }
@@ -150,4 +313,47 @@ mod tests {
         arr.blink_n(6);
         assert_eq!(arr.get_number_stones(), 22);
     }
+
+    #[test]
+    fn test_budget_step_limit() {
+        let mut arr = Arrangement::new(vec![125, 17]);
+        let budget = Budget::new().with_max_steps(6);
+        assert_eq!(
+            arr.blink_n_budgeted(25, &budget),
+            Some(StopReason::StepLimit)
+        );
+        assert_eq!(arr.blinks, 6);
+        assert_eq!(arr.get_number_stones(), 22);
+    }
+
+    #[test]
+    fn test_budget_cancel() {
+        let mut arr = Arrangement::new(vec![125, 17]);
+        let budget = Budget::new();
+        budget.clone().cancel();
+        assert_eq!(
+            arr.blink_n_budgeted(25, &budget),
+            Some(StopReason::Cancelled)
+        );
+        assert_eq!(arr.blinks, 0);
+        assert_eq!(arr.blink_n_budgeted(6, &Budget::new()), None);
+        assert_eq!(arr.get_number_stones(), 22);
+    }
+
+    #[test]
+    fn test_budget_from_args() {
+        let args: Vec<String> = vec!["--max-steps".into(), "3".into()];
+        let budget = Budget::from_args(&args).unwrap().unwrap();
+        assert_eq!(budget.check(2), None);
+        assert_eq!(budget.check(3), Some(StopReason::StepLimit));
+        assert!(Budget::from_args(&[]).unwrap().is_none());
+        for bad in [
+            &["--max-seconds", "-1"][..],
+            &["--max-seconds", "soon"],
+            &["--max-steps"],
+        ] {
+            let args: Vec<String> = bad.iter().map(|a| a.to_string()).collect();
+            assert!(Budget::from_args(&args).is_err());
+        }
+    }
 }
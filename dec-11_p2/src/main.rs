@@ -2,7 +2,10 @@
 use std::sync::{
     {Arc, Mutex},
 };
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
+use std::time::{Duration, Instant};
 
 fn main() {
     let max_level = 75;
@@ -10,36 +13,241 @@ fn main() {
     let inp = std::fs::read_to_string("input").unwrap();
     let values: Vec<u64> = inp.trim().split(" ").map(|x| x.parse().unwrap()).collect();
 
+    // Optional limits, e.g. `--max-seconds 60` or `--max-steps 1000000000` (steps per tree in
+    // this run, a resumed tree gets as many steps again)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let budget = match Budget::from_args(&args) {
+        Ok(Some(budget)) => budget,
+        Ok(None) => Budget::new(),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Ctrl-C stops every tree at its next budget check, saving its checkpoint, and still prints
+    // the partial sum, a second Ctrl-C quits right away
+    let token = budget.clone();
+    ctrlc::set_handler(move || {
+        if token.is_cancelled() {
+            std::process::exit(130);
+        }
+        token.cancel();
+    })
+    .expect("cannot install the Ctrl-C handler");
 
+    // Optional `--checkpoint DIR`: save every tree there once a minute and resume from it
+    let checkpoint_dir = args
+        .windows(2)
+        .find(|pair| pair[0] == "--checkpoint")
+        .map(|pair| PathBuf::from(&pair[1]));
+    if let Some(dir) = &checkpoint_dir {
+        std::fs::create_dir_all(dir).unwrap();
+    }
+    let input = input_hash(&inp);
 
     let total_sum = Arc::new(Mutex::new(0_u64));
     let mut handles = Vec::new();
 
     let now = std::time::Instant::now();
 
-    for val in values {
+    for (it, val) in values.into_iter().enumerate() {
         let total_sum = total_sum.clone();
+        let budget = budget.clone();
+        let checkpoint = checkpoint_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("tree-{}.txt", it)));
+        // resume before starting any thread, so a bad checkpoint stops the run right away
+        let resumed = checkpoint
+            .as_ref()
+            .map(|path| match Tree::load_checkpoint(path, input) {
+                Ok(tree) if tree.root == val && tree.max_level == max_level => {
+                    println!("Value: {} resumed at step {}", val, tree.steps);
+                    tree
+                }
+                Ok(tree) => {
+                    eprintln!(
+                        "checkpoint {}: walks {} to level {}, expected {} to level {}",
+                        path.display(),
+                        tree.root,
+                        tree.max_level,
+                        val,
+                        max_level
+                    );
+                    std::process::exit(1);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Tree::new(val, max_level),
+                Err(e) => {
+                    eprintln!("checkpoint {}: {}", path.display(), e);
+                    std::process::exit(1);
+                }
+            });
+        let budget = budget.resuming_at(resumed.as_ref().map_or(0, |tree| tree.steps));
         let hndl = thread::spawn(move || {
-            let mut tree = Tree::new(val, max_level);
-            tree.walk_through_tree();
-            println!("Value: {} done, sum: {}", val, tree.sum_max_reached);
+            let (tree, stopped) = match checkpoint.zip(resumed) {
+                Some((path, mut tree)) => {
+                    let stopped =
+                        tree.walk_with_checkpoints(&budget, &path, input, Duration::from_secs(60));
+                    (tree, stopped)
+                }
+                None => {
+                    let mut tree = Tree::new(val, max_level);
+                    let stopped = tree.walk_through_tree_budgeted(&budget);
+                    (tree, stopped)
+                }
+            };
+            match stopped {
+                None => println!("Value: {} done, sum: {}", val, tree.sum_max_reached),
+                Some(reason) => {
+                    // one tree out of budget means the total is partial anyway, stop the others
+                    budget.cancel();
+                    println!(
+                        "Value: {} stopped ({}) after {} steps at level {}, sum so far: {}",
+                        val, reason, tree.steps, tree.level, tree.sum_max_reached
+                    );
+                }
+            }
             loop {
                 if let Ok(mut sum) = total_sum.try_lock() {
                     *sum += tree.sum_max_reached;
                     break;
                 }
             }
+            stopped
         });
         handles.push(hndl);
     }
 
+    let mut stopped = None;
     for hndl in handles {
-        hndl.join().unwrap();
+        // report what ran out of budget rather than the cancellation it triggered in the others
+        match hndl.join().unwrap() {
+            Some(StopReason::Cancelled) => {
+                stopped.get_or_insert(StopReason::Cancelled);
+            }
+            Some(reason) => stopped = Some(reason),
+            None => (),
+        }
     }
 
     let elapsed_time = now.elapsed().as_millis() as f64 / 1000.0;
     let tsum = *total_sum.lock().unwrap();
-    println!("Max level: {}, sum: {}, seconds elapsed {}", max_level, tsum, elapsed_time);
+    match stopped {
+        None => println!(
+            "Max level: {}, sum: {}, seconds elapsed {}",
+            max_level, tsum, elapsed_time
+        ),
+        Some(reason) => println!(
+            "Max level: {}, stopped ({}), partial sum: {}, seconds elapsed {}",
+            max_level, reason, tsum, elapsed_time
+        ),
+    }
+}
+
+/// Why a budgeted computation stopped before it was done.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum StopReason {
+    Cancelled,
+    StepLimit,
+    TimeLimit,
+}
+
+impl std::fmt::Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StopReason::Cancelled => write!(f, "cancelled"),
+            StopReason::StepLimit => write!(f, "step limit"),
+            StopReason::TimeLimit => write!(f, "time limit"),
+        }
+    }
+}
+
+/// Step and time limits plus a cancellation token, checked cooperatively by the solver.
+#[derive(Debug, Clone, Default)]
+struct Budget {
+    max_steps: Option<u64>,
+    deadline: Option<Instant>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Budget {
+    fn new() -> Budget {
+        Budget::default()
+    }
+
+    fn with_max_steps(mut self, steps: u64) -> Budget {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    /// Stop after `limit` from now, or earlier if the budget already has an earlier deadline.
+    fn with_time_limit(mut self, limit: Duration) -> Budget {
+        let deadline = Instant::now() + limit;
+        self.deadline = Some(self.deadline.map_or(deadline, |dl| dl.min(deadline)));
+        self
+    }
+
+    /// Build a budget from `--max-steps N` and / or `--max-seconds N`, `None` if neither is given.
+    fn from_args(args: &[String]) -> Result<Option<Budget>, String> {
+        let mut budget: Option<Budget> = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--max-steps" => {
+                    let steps = args
+                        .next()
+                        .and_then(|v| v.parse().ok())
+                        .ok_or("--max-steps needs a whole number of steps")?;
+                    budget = Some(budget.unwrap_or_default().with_max_steps(steps));
+                }
+                "--max-seconds" => {
+                    let limit = args
+                        .next()
+                        .and_then(|v| v.parse().ok())
+                        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                        .ok_or("--max-seconds needs a number of seconds, 0 or more")?;
+                    budget = Some(budget.unwrap_or_default().with_time_limit(limit));
+                }
+                _ => (),
+            }
+        }
+        Ok(budget)
+    }
+
+    /// The budget for a walk that resumes after `steps` steps, so the step limit counts from there.
+    fn resuming_at(mut self, steps: u64) -> Budget {
+        self.max_steps = self.max_steps.map(|max| max.saturating_add(steps));
+        self
+    }
+
+    /// Ask everyone sharing this budget to stop at their next check.
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Check if we may continue after `steps` steps, otherwise return why not.
+    fn check(&self, steps: u64) -> Option<StopReason> {
+        if self.is_cancelled() {
+            Some(StopReason::Cancelled)
+        } else if self.max_steps.is_some_and(|max| steps >= max) {
+            Some(StopReason::StepLimit)
+        } else if self.deadline.is_some_and(|dl| Instant::now() >= dl) {
+            Some(StopReason::TimeLimit)
+        } else {
+            None
+        }
+    }
+}
+
+/// FNV-1a hash of the puzzle input, kept in every checkpoint.
+fn input_hash(data: &str) -> u64 {
+    data.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -51,6 +259,16 @@ enum NodeGoNext {
 }
 
 impl NodeGoNext {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "DownLeft" => Some(NodeGoNext::DownLeft),
+            "DownRight" => Some(NodeGoNext::DownRight),
+            "Up" => Some(NodeGoNext::Up),
+            "Done" => Some(NodeGoNext::Done),
+            _ => None,
+        }
+    }
+
     fn next(&self) -> Self {
         match self {
             NodeGoNext::DownLeft => NodeGoNext::DownRight,
@@ -61,36 +279,168 @@ impl NodeGoNext {
     }
 }
 
+#[derive(Debug, PartialEq)]
 struct Tree {
+    root: u64,
     value: u64,
     max_level: u64,
     sum_max_reached: u64,
     state: NodeGoNext,
     level: u64,
+    steps: u64,
     previous_values: Vec<u64>,
     previous_states: Vec<NodeGoNext>,
 }
 
+/// How many steps to walk between two full budget checks (clock and cancellation).
+const BUDGET_CHECK_EVERY: u64 = 1 << 16;
+
 impl Tree {
     fn new(value: u64, max_level: u64) -> Self {
         Tree {
+            root: value,
             value,
             max_level,
             sum_max_reached: 0,
             state: NodeGoNext::DownLeft,
             level: 0,
+            steps: 0,
             previous_values: Vec::new(),
             previous_states: Vec::new(),
         }
     }
 
+    #[cfg(test)]
     fn walk_through_tree(&mut self) {
         while self.state != NodeGoNext::Done {
             self.next();
         }
     }
 
+    /// Walk through the tree until done or until the budget runs out.
+    ///
+    /// Returns `None` if the walk finished, otherwise the reason for stopping. The tree can be
+    /// walked further later on, `sum_max_reached` holds the nodes at max level found so far.
+    fn walk_through_tree_budgeted(&mut self, budget: &Budget) -> Option<StopReason> {
+        while self.state != NodeGoNext::Done {
+            if self.steps.is_multiple_of(BUDGET_CHECK_EVERY)
+                || budget.max_steps.is_some_and(|max| self.steps >= max)
+            {
+                if let Some(reason) = budget.check(self.steps) {
+                    return Some(reason);
+                }
+            }
+            self.next();
+        }
+        None
+    }
+
+    /// Walk through the tree like `walk_through_tree_budgeted`, but save a checkpoint to `path`
+    /// every `interval` and once more when stopping, done or not. `input` is the hash of the
+    /// puzzle input, see `input_hash`.
+    fn walk_with_checkpoints(
+        &mut self,
+        budget: &Budget,
+        path: &Path,
+        input: u64,
+        interval: Duration,
+    ) -> Option<StopReason> {
+        loop {
+            let chunk = budget.clone().with_time_limit(interval);
+            let stopped = self.walk_through_tree_budgeted(&chunk);
+            self.save_checkpoint(path, input).unwrap();
+            match stopped {
+                // only the checkpoint interval is up, keep going
+                Some(StopReason::TimeLimit) if budget.check(self.steps).is_none() => (),
+                _ => return stopped,
+            }
+        }
+    }
+
+    /// Save the complete walk state as `key value` lines, so the walk can be resumed later.
+    /// The first line is the hash of the puzzle input the walk belongs to. The file is written
+    /// next to `path` and then moved over it, so it is never left half written.
+    fn save_checkpoint(&self, path: &Path, input: u64) -> std::io::Result<()> {
+        let values: Vec<String> = self.previous_values.iter().map(|v| v.to_string()).collect();
+        let states: Vec<String> = self
+            .previous_states
+            .iter()
+            .map(|s| format!("{:?}", s))
+            .collect();
+        let content = format!(
+            "input {:016x}\nroot {}\nvalue {}\nmax_level {}\nsum_max_reached {}\nstate {:?}\nlevel {}\nsteps {}\nprevious_values {}\nprevious_states {}\n",
+            input,
+            self.root,
+            self.value,
+            self.max_level,
+            self.sum_max_reached,
+            self.state,
+            self.level,
+            self.steps,
+            values.join(" "),
+            states.join(" "),
+        );
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(&tmp, path)
+    }
+
+    /// Load a walk state that was saved with `save_checkpoint` for the same `input`.
+    fn load_checkpoint(path: &Path, input: u64) -> std::io::Result<Tree> {
+        let content = std::fs::read_to_string(path)?;
+        let invalid = |what: &str| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("bad checkpoint: {}", what),
+            )
+        };
+
+        let mut tree = Tree::new(0, 0);
+        let mut saved_input = None;
+        for line in content.lines() {
+            let (key, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let number = || rest.parse::<u64>().map_err(|_| invalid(key));
+            match key {
+                "input" => {
+                    saved_input = Some(u64::from_str_radix(rest, 16).map_err(|_| invalid(key))?)
+                }
+                "root" => tree.root = number()?,
+                "value" => tree.value = number()?,
+                "max_level" => tree.max_level = number()?,
+                "sum_max_reached" => tree.sum_max_reached = number()?,
+                "state" => tree.state = NodeGoNext::from_name(rest).ok_or_else(|| invalid(key))?,
+                "level" => tree.level = number()?,
+                "steps" => tree.steps = number()?,
+                "previous_values" => {
+                    tree.previous_values = rest
+                        .split_whitespace()
+                        .map(|v| v.parse().map_err(|_| invalid(key)))
+                        .collect::<Result<_, _>>()?
+                }
+                "previous_states" => {
+                    tree.previous_states = rest
+                        .split_whitespace()
+                        .map(|s| NodeGoNext::from_name(s).ok_or_else(|| invalid(key)))
+                        .collect::<Result<_, _>>()?
+                }
+                _ => return Err(invalid(key)),
+            }
+        }
+        match saved_input {
+            None => return Err(invalid("no input hash")),
+            Some(saved) if saved != input => return Err(invalid("saved for a different input")),
+            _ => (),
+        }
+        if tree.previous_values.len() as u64 != tree.level
+            || tree.previous_states.len() as u64 != tree.level
+        {
+            return Err(invalid("stack depth does not match level"));
+        }
+        Ok(tree)
+    }
+
     fn next(&mut self) {
+        self.steps += 1;
         match self.state {
             NodeGoNext::DownLeft => {
                 self.level += 1;
@@ -138,7 +488,7 @@ impl Tree {
             Some(1)
         } else {
             let len = self.num_digits();
-            if len % 2 == 0 {
+            if len.is_multiple_of(2) {
                 Some(self.value_left_part(len / 2))
             } else {
                 Some(self.value * 2024)
@@ -151,7 +501,7 @@ impl Tree {
             None
         } else {
             let len = self.num_digits();
-            if len % 2 == 0 {
+            if len.is_multiple_of(2) {
                 Some(self.value_right_part(len / 2))
             } else {
                 None
@@ -223,4 +573,100 @@ mod tests {
 
         assert_eq!(total_sum, 55312);
     }
+
+    #[test]
+    fn test_budget_step_limit_and_resume() {
+        let mut tree = Tree::new(125, 25);
+        let budget = Budget::new().with_max_steps(1000);
+        assert_eq!(
+            tree.walk_through_tree_budgeted(&budget),
+            Some(StopReason::StepLimit)
+        );
+        assert_eq!(tree.steps, 1000);
+        assert!(tree.sum_max_reached > 0);
+
+        // walk the rest without limits
+        assert_eq!(tree.walk_through_tree_budgeted(&Budget::new()), None);
+        let mut full = Tree::new(125, 25);
+        full.walk_through_tree();
+        assert_eq!(tree.sum_max_reached, full.sum_max_reached);
+    }
+
+    #[test]
+    fn test_budget_cancel() {
+        let budget = Budget::new();
+        let token = budget.clone();
+        token.cancel();
+        let mut tree = Tree::new(17, 25);
+        assert_eq!(
+            tree.walk_through_tree_budgeted(&budget),
+            Some(StopReason::Cancelled)
+        );
+        assert_eq!(tree.steps, 0);
+    }
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let path = std::env::temp_dir().join(format!("dec-11-tree-{}.txt", std::process::id()));
+        let mut tree = Tree::new(125, 25);
+        tree.walk_through_tree_budgeted(&Budget::new().with_max_steps(12345));
+        tree.save_checkpoint(&path, input_hash("125 17")).unwrap();
+
+        let mut resumed = Tree::load_checkpoint(&path, input_hash("125 17")).unwrap();
+        let other_input = Tree::load_checkpoint(&path, input_hash("125 18"));
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(
+            &path,
+            content.lines().skip(1).collect::<Vec<_>>().join("\n"),
+        )
+        .unwrap();
+        let without_hash = Tree::load_checkpoint(&path, input_hash("125 17"));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(resumed, tree);
+        assert!(other_input
+            .unwrap_err()
+            .to_string()
+            .contains("different input"));
+        assert!(without_hash.is_err());
+
+        resumed.walk_through_tree_budgeted(&Budget::new());
+        let mut full = Tree::new(125, 25);
+        full.walk_through_tree();
+        assert_eq!(resumed.sum_max_reached, full.sum_max_reached);
+    }
+
+    #[test]
+    fn test_step_limit_after_resume() {
+        let mut tree = Tree::new(125, 25);
+        tree.walk_through_tree_budgeted(&Budget::new().with_max_steps(12345));
+        // a smaller limit than the steps already taken still lets the resumed walk go on
+        let budget = Budget::new().with_max_steps(1000).resuming_at(tree.steps);
+        assert_eq!(
+            tree.walk_through_tree_budgeted(&budget),
+            Some(StopReason::StepLimit)
+        );
+        assert_eq!(tree.steps, 13345);
+        assert_eq!(Budget::new().resuming_at(tree.steps).max_steps, None);
+    }
+
+    #[test]
+    fn test_budget_from_args() {
+        let args = |args: &[&str]| {
+            Budget::from_args(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+        };
+        let budget = args(&["--checkpoint", "dir", "--max-steps", "3"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(budget.check(3), Some(StopReason::StepLimit));
+        assert!(args(&["--checkpoint", "dir"]).unwrap().is_none());
+        assert!(args(&["--max-seconds", "-1"]).is_err());
+        assert!(args(&["--max-steps", "many"]).is_err());
+    }
+
+    #[test]
+    fn test_budget_earliest_deadline_wins() {
+        let budget = Budget::new().with_time_limit(Duration::ZERO);
+        let budget = budget.with_time_limit(Duration::from_secs(3600));
+        assert_eq!(budget.check(0), Some(StopReason::TimeLimit));
+    }
 }
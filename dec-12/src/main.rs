@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::slice::Iter;
 
 fn main() {
@@ -9,6 +9,10 @@ fn main() {
     println!("Price part 1: {}", prices);
 
     println!("Price part 2: {}", map.calc_price_p2());
+
+    if std::env::args().any(|arg| arg == "--grid") {
+        print!("{}", map.render_plots());
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -58,11 +62,13 @@ struct GardenPlot {
     perimeter_coordinates: HashSet<Coordinate>,
     area: u64,
     perimeter: u64,
+    #[allow(dead_code)]
     perimeter_coordinates_p1: HashSet<Coordinate>,
     sides_p2: u64,
 }
 
 impl GardenPlot {
+    #[allow(dead_code)]
     fn new(name: char) -> Self {
         Self {
             name,
@@ -333,6 +339,22 @@ impl Map {
         None
     }
 
+    /// Render the map from the plots that were found, fields that are in no plot show as `.`.
+    fn render_plots(&self) -> String {
+        let mut grid: Vec<Vec<char>> = self.data.iter().map(|row| vec!['.'; row.len()]).collect();
+        for plot in self.plots.iter() {
+            for crd in plot.coordinates.iter() {
+                grid[crd.row][crd.col] = plot.name;
+            }
+        }
+        let mut out = String::new();
+        for row in grid {
+            out.extend(row);
+            out.push('\n');
+        }
+        out
+    }
+
     /// Find all connected regions and add them to the plots vector.
     fn find_all_connected(&mut self) {
         while let Some(start_coord) = self.find_next_starting_coordinate() {
@@ -459,6 +481,7 @@ fn next_tile_from_current(curr: &Coordinate, dir: &WalkDir) -> Option<Coordinate
     }
 }
 
+#[allow(dead_code)]
 fn number_connected_sides_top_bottom(perimeter: &HashSet<Coordinate>) -> u64 {
     let mut min_row = 0;
     let mut max_row = usize::MAX;
@@ -541,6 +564,13 @@ MMMISSJEEE";
         assert_eq!(prices, 1930);
     }
 
+    #[test]
+    fn test_render_plots() {
+        let mut map = Map::new(DATA);
+        map.find_all_connected();
+        assert_eq!(map.render_plots(), format!("{}\n", DATA));
+    }
+
     #[test]
     fn test_p1_2() {
         let data = "OOOOO
@@ -599,6 +629,7 @@ AAAAAA";
     }
 
     #[test]
+    #[allow(clippy::identity_op)]
     fn test_p2_4() {
         let data = "AAAAAA
 AABBAA
@@ -619,6 +650,7 @@ AAAAAA";
     }
 
     #[test]
+    #[allow(clippy::identity_op)]
     fn test_p2_5() {
         let data = "BAA
 AAA
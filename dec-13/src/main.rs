@@ -70,7 +70,11 @@ struct ClawMachine {
 
 impl ClawMachine {
     fn new_w_btn(btn_a: Button, btn_b: Button, target: Coord) -> ClawMachine {
-        ClawMachine { btn_a, btn_b, target }
+        ClawMachine {
+            btn_a,
+            btn_b,
+            target,
+        }
     }
 
     /// Returns None if no solution exist, otherwise clicks button A and B as array
@@ -122,20 +126,107 @@ impl AllClawMachines {
     }
 
     fn total_price(&self) -> i64 {
-        self.machines.iter().map(|m| m.price().unwrap_or_default()).sum()
+        self.machines
+            .iter()
+            .map(|m| m.price().unwrap_or_default())
+            .sum()
+    }
+
+    /// Explain for every machine whether it can be solved, and with which presses.
+    fn explain(&self, part: u32) -> Vec<MachineExplanation> {
+        self.machines
+            .iter()
+            .enumerate()
+            .map(|(index, m)| MachineExplanation {
+                index,
+                part,
+                target: [m.target.x, m.target.y],
+                presses: m.solution(),
+                price: m.price(),
+            })
+            .collect()
+    }
+}
+
+/// Button presses and price for one machine in one part, `None` if it cannot be solved.
+#[derive(Debug, PartialEq)]
+struct MachineExplanation {
+    index: usize,
+    part: u32,
+    target: [i64; 2],
+    presses: Option<[i64; 2]>,
+    price: Option<i64>,
+}
+
+impl MachineExplanation {
+    fn table_row(&self) -> String {
+        let target = format!("X={}, Y={}", self.target[0], self.target[1]);
+        match (self.presses, self.price) {
+            (Some([na, nb]), Some(price)) => format!(
+                "{:>7}  {:>4}  {:<34}  {:>14}  {:>14}  {}",
+                self.index, self.part, target, na, nb, price
+            ),
+            _ => format!(
+                "{:>7}  {:>4}  {:<34}  {:>14}  {:>14}  not solvable",
+                self.index, self.part, target, "-", "-"
+            ),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let null = || "null".to_string();
+        json_object(&[
+            ("machine", self.index.to_string()),
+            ("part", self.part.to_string()),
+            ("target", json_array(self.target)),
+            ("presses", self.presses.map_or_else(null, json_array)),
+            ("price", self.price.map_or_else(null, |p| p.to_string())),
+        ])
     }
 }
 
+/// A JSON object from field names and values that are already JSON.
+fn json_object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| format!("\"{}\": {}", name, value))
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// A JSON array of numbers.
+fn json_array(items: impl IntoIterator<Item = impl std::fmt::Display>) -> String {
+    let items: Vec<String> = items.into_iter().map(|item| item.to_string()).collect();
+    format!("[{}]", items.join(", "))
+}
+
 fn main() {
     let s = std::fs::read_to_string("input").unwrap();
     let mut machines = AllClawMachines::new(&s);
     println!("Price part 1: {}", machines.total_price());
+    let mut explanations = machines.explain(1);
 
     for mach in machines.machines.iter_mut() {
         mach.target.x += PRICE_OFFSET;
         mach.target.y += PRICE_OFFSET;
     }
     println!("Price part 2: {}", machines.total_price());
+    explanations.extend(machines.explain(2));
+
+    // Optional `--explain` / `--explain-json`: list which machines were solvable and how
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--explain") {
+        println!(
+            "{:>7}  {:>4}  {:<34}  {:>14}  {:>14}  price",
+            "machine", "part", "target", "presses A", "presses B"
+        );
+        for expl in &explanations {
+            println!("{}", expl.table_row());
+        }
+    } else if args.iter().any(|a| a == "--explain-json") {
+        let rows: Vec<String> = explanations.iter().map(|e| e.to_json()).collect();
+        println!("[\n  {}\n]", rows.join(",\n  "));
+    }
 }
 
 #[cfg(test)]
@@ -163,4 +254,23 @@ Prize: X=18641, Y=10279";
         let machines = AllClawMachines::new(DATA);
         assert_eq!(machines.total_price(), 480);
     }
+
+    #[test]
+    fn test_explain() {
+        let machines = AllClawMachines::new(DATA);
+        let expl = machines.explain(1);
+        assert_eq!(expl[0].presses, Some([80, 40]));
+        assert_eq!(expl[0].price, Some(280));
+        assert_eq!(expl[1].presses, None);
+        assert_eq!(
+            expl[1].to_json(),
+            "{\"machine\": 1, \"part\": 1, \"target\": [12748, 12176], \"presses\": null, \"price\": null}"
+        );
+        let solvable: Vec<usize> = expl
+            .iter()
+            .filter(|e| e.price.is_some())
+            .map(|e| e.index)
+            .collect();
+        assert_eq!(solvable, vec![0, 2]);
+    }
 }
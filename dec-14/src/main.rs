@@ -1,17 +1,51 @@
+use std::path::{Path, PathBuf};
+
 fn main() {
     let data = std::fs::read_to_string("input").unwrap();
     let mut map = Map::new_from_data(&data, 101, 103);
     map.move_robots(100);
     println!("Part 1: {}", map.count_robots_quadrants_mult());
 
+    // Optional `--checkpoint FILE`: save the robots every 1000 steps and resume from there
+    // Optional `--grid`: print the robots after the last step
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let checkpoint = args
+        .windows(2)
+        .find(|pair| pair[0] == "--checkpoint")
+        .map(|pair| PathBuf::from(&pair[1]));
+
     // Tree is at frame 6512 - brute forced by observing...
-    let mut map = MapP2::new_from_data(&data);
-    for _ in 0..10000 {
+    let loaded = checkpoint
+        .as_ref()
+        .map(|path| (path, MapP2::load_checkpoint(path, &data)));
+    let mut map = match loaded {
+        Some((_, Ok(map))) => {
+            println!("Resuming at step {}", map.steps);
+            map
+        }
+        Some((path, Err(e))) if e.kind() != std::io::ErrorKind::NotFound => {
+            eprintln!("checkpoint {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+        _ => MapP2::new_from_data(&data),
+    };
+    while map.steps < 10000 {
         map.next_step();
         map.update_map();
         if map.steps == 6512 {
             println!("{}", map);
         }
+        if let Some(path) = &checkpoint {
+            if map.steps % 1000 == 0 {
+                if let Err(e) = map.save_checkpoint(path, &data) {
+                    eprintln!("checkpoint {}: {}", path.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+    if args.iter().any(|arg| arg == "--grid") {
+        println!("{}", map);
     }
 }
 
@@ -106,6 +140,27 @@ impl Robot {
         }
     }
 
+    /// Like `new_from_line`, but `None` instead of a panic for a malformed line.
+    fn parse_line(s: &str) -> Option<Robot> {
+        let coord = |s: &str, prefix| {
+            let (x, y) = s.strip_prefix(prefix)?.split_once(',')?;
+            Some(Coord::new(x.parse().ok()?, y.parse().ok()?))
+        };
+        let (pos, vel) = s.trim().split_once(' ')?;
+        Some(Robot {
+            position: coord(pos, "p=")?,
+            velocity: coord(vel, "v=")?,
+        })
+    }
+
+    /// Write the robot in the same `p=x,y v=x,y` format it was read from.
+    fn to_line(&self) -> String {
+        format!(
+            "p={},{} v={},{}",
+            self.position.x, self.position.y, self.velocity.x, self.velocity.y
+        )
+    }
+
     fn move_robot(&mut self, time: i64) {
         self.position = self.position + self.velocity * time;
     }
@@ -181,6 +236,13 @@ impl Map {
     }
 }
 
+/// FNV-1a hash of the input, to recognise the checkpoints made for it.
+fn input_hash(data: &str) -> u64 {
+    data.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
 struct MapP2 {
     robots: Vec<Robot>,
     map: [[u64; 101]; 103],
@@ -211,6 +273,54 @@ impl MapP2 {
         self.steps += 1;
     }
 
+    /// Save the step count and all robots, so the simulation can be resumed later.
+    ///
+    /// The robots are written in the input format, after a line with a hash of the input `data`
+    /// they started from and a line with the steps. The file is replaced only once complete.
+    fn save_checkpoint(&self, path: &Path, data: &str) -> std::io::Result<()> {
+        let mut content = format!("input {:016x}\nsteps {}\n", input_hash(data), self.steps);
+        for robot in self.robots.iter() {
+            content.push_str(&robot.to_line());
+            content.push('\n');
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(&tmp, path)
+    }
+
+    /// Load a simulation that was saved with `save_checkpoint` for the same input `data`.
+    fn load_checkpoint(path: &Path, data: &str) -> std::io::Result<MapP2> {
+        let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+        let content = std::fs::read_to_string(path)?;
+        let mut lines = content.lines();
+        let hash = lines
+            .next()
+            .and_then(|l| l.strip_prefix("input "))
+            .and_then(|h| u64::from_str_radix(h, 16).ok())
+            .ok_or_else(|| invalid("checkpoint without input hash"))?;
+        if hash != input_hash(data) {
+            return Err(invalid("checkpoint belongs to a different input"));
+        }
+        let steps = lines
+            .next()
+            .and_then(|l| l.strip_prefix("steps "))
+            .and_then(|s| s.parse::<u64>().ok())
+            .ok_or_else(|| invalid("checkpoint without steps"))?;
+        let robots = lines
+            .map(Robot::parse_line)
+            .collect::<Option<Vec<Robot>>>()
+            .ok_or_else(|| invalid("malformed robot in checkpoint"))?;
+        if robots.len() != data.lines().count() {
+            return Err(invalid("checkpoint has the wrong number of robots"));
+        }
+
+        let mut map = MapP2::new_from_data("");
+        map.robots = robots;
+        map.steps = steps;
+        map.update_map();
+        Ok(map)
+    }
+
     fn update_map(&mut self) {
         self.map.iter_mut().for_each(|m| *m = [0; 101]);
         for robot in self.robots.iter() {
@@ -229,7 +339,7 @@ impl std::fmt::Display for MapP2 {
                     write!(f, ".")?;
                 }
             }
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
         write!(f, "Steps: {}", self.steps)
     }
@@ -265,4 +375,36 @@ p=9,5 v=-3,-3";
         map.move_robots(100);
         assert_eq!(map.count_robots_quadrants_mult(), 12);
     }
+
+    #[test]
+    fn test_p2_checkpoint_resume() {
+        let path = std::env::temp_dir().join(format!("dec-14-map-{}.txt", std::process::id()));
+        let mut map = MapP2::new_from_data(DATA);
+        for _ in 0..50 {
+            map.next_step();
+        }
+        map.save_checkpoint(&path, DATA).unwrap();
+
+        let mut resumed = MapP2::load_checkpoint(&path, DATA).unwrap();
+        let other_input = MapP2::load_checkpoint(&path, &DATA.replacen("p=0,4", "p=1,4", 1));
+        std::fs::write(&path, "input 0\nsteps 1\n").unwrap();
+        let wrong_hash = MapP2::load_checkpoint(&path, DATA);
+        let content = format!("input {:016x}\nsteps 1\np=0,4 v=3\n", input_hash(DATA));
+        std::fs::write(&path, content).unwrap();
+        let broken = MapP2::load_checkpoint(&path, DATA);
+        std::fs::remove_file(&path).unwrap();
+        assert!(other_input.is_err());
+        assert!(wrong_hash.is_err());
+        assert!(broken.is_err());
+        assert!(matches!(
+            MapP2::load_checkpoint(&path, DATA),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound
+        ));
+        assert_eq!(resumed.steps, 50);
+        for _ in 0..50 {
+            map.next_step();
+            resumed.next_step();
+        }
+        assert_eq!(format!("{}", resumed), format!("{}", map));
+    }
 }
@@ -1,3 +1,4 @@
+#[cfg(test)]
 const DATA_SMALL: &str = "########
 #..O.O.#
 ##@.O..#
@@ -22,6 +23,10 @@ fn main() {
     map.move_all();
 
     println!("Part 1: {}", map.sum_gps());
+
+    if std::env::args().any(|arg| arg == "--grid") {
+        print!("{}", map);
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]